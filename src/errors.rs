@@ -8,6 +8,12 @@ pub enum SprsError {
     NonSortedIndices,
     UnsortedIndptr,
     SingularMatrix,
+    OutOfBoundsIndex,
+    BadIndptrLength,
+    DataIndicesMismatch,
+    BadNnzCount,
+    OutOfBoundsIndptr,
+    IncompatibleDimensions,
 }
 
 use self::SprsError::*;
@@ -18,6 +24,12 @@ impl SprsError {
             NonSortedIndices => "a vector's indices are not sorted",
             UnsortedIndptr => "indptr is not sorted",
             SingularMatrix => "matrix is singular",
+            OutOfBoundsIndex => "an index is out of bounds",
+            BadIndptrLength => "indptr length does not match dimension",
+            DataIndicesMismatch => "indices and data lengths do not match",
+            BadNnzCount => "indices length and indptr's nnz do not match",
+            OutOfBoundsIndptr => "an indptr value is out of bounds",
+            IncompatibleDimensions => "dimensions do not match",
         }
     }
 }
@@ -2,14 +2,16 @@
 
 use std::path::Path;
 use std::io;
-use std::io::{BufRead, Write, Seek, SeekFrom};
+use std::io::{BufRead, Read, Write, Seek, SeekFrom};
 use std::fs::File;
 use std::error::Error;
 use std::fmt;
+use std::ops::Deref;
 
 use num_traits::cast::NumCast;
 
-use sparse::{TriMatI, SparseMat};
+use sparse::{TriMatI, SparseMat, CsMatBase, CsMatI, CsMatViewI};
+use sparse::CompressedStorage::{self, CSR, CSC};
 use indexing::SpIndex;
 use num_kinds::{PrimitiveKind, NumKind};
 
@@ -18,6 +20,7 @@ pub enum IoError {
     Io(io::Error),
     BadMatrixMarketFile,
     UnsupportedMatrixMarketFormat,
+    BadBinaryFile,
 }
 
 use self::IoError::*;
@@ -30,6 +33,8 @@ impl fmt::Display for IoError {
                 write!(f, "Bad matrix market file."),
             IoError::UnsupportedMatrixMarketFormat =>
                 write!(f, "Bad matrix market file."),
+            IoError::BadBinaryFile =>
+                write!(f, "Bad binary sparse matrix file."),
         }
     }
 }
@@ -40,6 +45,7 @@ impl Error for IoError {
             IoError::Io(ref err) => err.description(),
             IoError::BadMatrixMarketFile => "bad matrix market file",
             IoError::UnsupportedMatrixMarketFormat => "unsupported format",
+            IoError::BadBinaryFile => "bad binary sparse matrix file",
         }
     }
 
@@ -48,6 +54,7 @@ impl Error for IoError {
             IoError::Io(ref err) => Some(err),
             IoError::BadMatrixMarketFile => None,
             IoError::UnsupportedMatrixMarketFormat => None,
+            IoError::BadBinaryFile => None,
         }
     }
 }
@@ -69,6 +76,10 @@ impl PartialEq for IoError {
                 IoError::UnsupportedMatrixMarketFormat => true,
                 _ => false,
             },
+            IoError::BadBinaryFile => match *rhs {
+                IoError::BadBinaryFile => true,
+                _ => false,
+            },
             _ => false,
         }
     }
@@ -129,6 +140,33 @@ where I: SpIndex,
     let mm_file = mm_file.as_ref();
     let f = File::open(mm_file)?;
     let mut reader = io::BufReader::new(f);
+    read_matrix_market_from_bufread(&mut reader)
+}
+
+/// Read a sparse matrix in the Matrix Market format from an in-memory
+/// buffer or any other buffered reader, and return a corresponding triplet
+/// matrix.
+///
+/// See `read_matrix_market` for the supported formats.
+///
+/// # Example
+///
+/// ```rust
+/// use sprs::io::read_matrix_market_from_bufread;
+/// let mm = "%%MatrixMarket matrix coordinate real general\n\
+///           2 2 1\n\
+///           2 1 3.5\n";
+/// let mat = read_matrix_market_from_bufread::<f64, usize, _>(
+///     &mut mm.as_bytes()
+/// ).unwrap();
+/// assert_eq!(mat.to_csr().get(1, 0), Some(&3.5));
+/// ```
+pub fn read_matrix_market_from_bufread<N, I, R>(reader: &mut R)
+    -> Result<TriMatI<N, I>, IoError>
+where I: SpIndex,
+      N: NumCast + Clone,
+      R: BufRead,
+{
     // MatrixMarket format specifies lines of at most 1024 chars
     let mut line = String::with_capacity(1024);
 
@@ -148,7 +186,10 @@ where I: SpIndex,
     loop {
         line.clear();
         let len = reader.read_line(&mut line)?;
-        if len == 0 || line.starts_with("%") {
+        if len == 0 {
+            return Err(BadMatrixMarketFile);
+        }
+        if line.starts_with("%") || line.split_whitespace().next().is_none() {
             continue;
         } else {
             break;
@@ -269,9 +310,37 @@ where I: 'a + SpIndex + fmt::Display,
       M: IntoIterator<Item=(&'a N, (I, I))> + SparseMat,
       P: AsRef<Path>,
 {
-    let (rows, cols, nnz) = (mat.rows(), mat.cols(), mat.nnz());
     let f = File::create(path)?;
     let mut writer = io::BufWriter::new(f);
+    write_matrix_market_to_writer(&mut writer, mat)
+}
+
+/// Write a sparse matrix into the matrix market format, using any writer,
+/// such as an in-memory buffer.
+///
+/// The matrix is written using many small writes, so unbuffered writers
+/// such as a `File` should be wrapped in a `BufWriter`.
+///
+/// # Example
+///
+/// ```rust
+/// use sprs::CsMat;
+/// let eye: CsMat<f64> = CsMat::eye(2);
+/// let mut buf = Vec::new();
+/// sprs::io::write_matrix_market_to_writer(&mut buf, &eye).unwrap();
+/// let written = String::from_utf8(buf).unwrap();
+/// assert!(written.starts_with("%%MatrixMarket matrix coordinate real"));
+/// assert!(written.ends_with("2 2 2\n1 1 1\n2 2 1\n"));
+/// ```
+pub fn write_matrix_market_to_writer<'a, N, I, M, W>(writer: &mut W,
+                                                     mat: M)
+    -> Result<(), io::Error>
+where I: 'a + SpIndex + fmt::Display,
+      N: 'a + PrimitiveKind + Copy + fmt::Display,
+      M: IntoIterator<Item=(&'a N, (I, I))> + SparseMat,
+      W: Write,
+{
+    let (rows, cols, nnz) = (mat.rows(), mat.cols(), mat.nnz());
 
     // header
     let data_type = match N::num_kind() {
@@ -396,12 +465,178 @@ where I: 'a + SpIndex + fmt::Display,
     Ok(())
 }
 
+/// Magic bytes starting the binary format written by `write_binary`
+const BINARY_MAGIC: &[u8; 8] = b"SPRSCSMT";
+
+fn write_le<W: Write>(writer: &mut W, bits: u64, nbytes: usize)
+    -> Result<(), io::Error>
+{
+    let mut buf = [0u8; 8];
+    for (i, byte) in buf.iter_mut().enumerate().take(nbytes) {
+        *byte = (bits >> (8 * i)) as u8;
+    }
+    writer.write_all(&buf[..nbytes])
+}
+
+fn read_le<R: Read>(reader: &mut R, nbytes: usize) -> Result<u64, io::Error> {
+    let mut buf = [0u8; 8];
+    reader.read_exact(&mut buf[..nbytes])?;
+    Ok(buf[..nbytes].iter().rev().fold(0, |acc, &b| (acc << 8) | b as u64))
+}
+
+/// Read a 64 bits integer, checking that it can be represented both as an
+/// `usize` and as the requested integer type
+fn read_checked_index<I, R>(reader: &mut R) -> Result<I, IoError>
+where I: NumCast,
+      R: Read,
+{
+    let val = read_le(reader, 8)?;
+    let _: usize = NumCast::from(val).ok_or(BadBinaryFile)?;
+    NumCast::from(val).ok_or(BadBinaryFile)
+}
+
+/// Scalar types that can be written in the binary format of `write_binary`,
+/// as little endian bytes.
+pub trait BinaryScalar: Sized {
+    /// Write this scalar as little endian bytes
+    fn write_le<W: Write>(&self, writer: &mut W) -> Result<(), io::Error>;
+
+    /// Read a scalar from little endian bytes
+    fn read_le<R: Read>(reader: &mut R) -> Result<Self, io::Error>;
+}
+
+macro_rules! binary_scalar_impl {
+    ($prim: ty, $nbytes: expr, $to_bits: expr, $from_bits: expr) => (
+        impl BinaryScalar for $prim {
+            fn write_le<W: Write>(&self, writer: &mut W)
+                -> Result<(), io::Error>
+            {
+                write_le(writer, $to_bits(*self), $nbytes)
+            }
+
+            fn read_le<R: Read>(reader: &mut R) -> Result<$prim, io::Error> {
+                read_le(reader, $nbytes).map($from_bits)
+            }
+        }
+    )
+}
+
+binary_scalar_impl!(f32, 4, |x: f32| x.to_bits() as u64,
+                    |x: u64| f32::from_bits(x as u32));
+binary_scalar_impl!(f64, 8, |x: f64| x.to_bits(), f64::from_bits);
+binary_scalar_impl!(i32, 4, |x: i32| x as u32 as u64, |x: u64| x as u32 as i32);
+binary_scalar_impl!(u32, 4, |x: u32| x as u64, |x: u64| x as u32);
+binary_scalar_impl!(i64, 8, |x: i64| x as u64, |x: u64| x as i64);
+binary_scalar_impl!(u64, 8, |x: u64| x, |x: u64| x);
+
+/// Write a compressed sparse matrix in a compact binary format.
+///
+/// The format starts with a header made of 8 magic bytes, a storage byte
+/// (0 for CSR, 1 for CSC), and the number of rows, columns and non-zeros
+/// as 64 bits integers. It is followed by the `indptr` and `indices`
+/// arrays, as 64 bits integers, and by the `data` array. All values are
+/// written in little endian order.
+///
+/// The matrix can be read back with `read_binary`.
+///
+/// # Example
+///
+/// ```rust
+/// use sprs::CsMat;
+/// use sprs::io::{read_binary, write_binary};
+/// let eye: CsMat<f64> = CsMat::eye(3);
+/// let mut buf = Vec::new();
+/// write_binary(&mut buf, &eye).unwrap();
+/// let res: CsMat<f64> = read_binary(&mut &buf[..]).unwrap();
+/// assert_eq!(res, eye);
+/// ```
+pub fn write_binary<N, I, IpS, IS, DS, W>(writer: &mut W,
+                                          mat: &CsMatBase<N, I, IpS, IS, DS>)
+    -> Result<(), io::Error>
+where N: BinaryScalar,
+      I: SpIndex,
+      IpS: Deref<Target=[I]>,
+      IS: Deref<Target=[I]>,
+      DS: Deref<Target=[N]>,
+      W: Write,
+{
+    writer.write_all(BINARY_MAGIC)?;
+    let storage_byte = match mat.storage() {
+        CSR => 0,
+        CSC => 1,
+    };
+    writer.write_all(&[storage_byte])?;
+    write_le(writer, mat.rows() as u64, 8)?;
+    write_le(writer, mat.cols() as u64, 8)?;
+    write_le(writer, mat.nnz() as u64, 8)?;
+    for &ind in mat.indptr().iter().chain(mat.indices().iter()) {
+        write_le(writer, ind.index() as u64, 8)?;
+    }
+    for val in mat.data() {
+        val.write_le(writer)?;
+    }
+    Ok(())
+}
+
+/// Read a compressed sparse matrix written by `write_binary`.
+///
+/// # Errors
+///
+/// - `BadBinaryFile` if the header is invalid, if a size or an index does
+///   not fit in the index type `I`, or if the arrays do not describe a
+///   valid compressed sparse matrix.
+/// - `Io` on read errors, including a premature end of the input.
+pub fn read_binary<N, I, R>(reader: &mut R) -> Result<CsMatI<N, I>, IoError>
+where N: BinaryScalar + Clone,
+      I: SpIndex,
+      R: Read,
+{
+    let mut magic = [0u8; 8];
+    reader.read_exact(&mut magic)?;
+    if &magic != BINARY_MAGIC {
+        return Err(BadBinaryFile);
+    }
+    let storage = match read_le(reader, 1)? {
+        0 => CompressedStorage::CSR,
+        1 => CompressedStorage::CSC,
+        _ => return Err(BadBinaryFile),
+    };
+    let rows: usize = read_checked_index(reader)?;
+    let cols: usize = read_checked_index(reader)?;
+    let nnz: usize = read_checked_index(reader)?;
+    let outer_dims = match storage {
+        CSR => rows,
+        CSC => cols,
+    };
+    let mut read_indices = |len: usize| -> Result<Vec<I>, IoError> {
+        let mut res = Vec::new();
+        for _ in 0..len {
+            res.push(read_checked_index(reader)?);
+        }
+        Ok(res)
+    };
+    let indptr = read_indices(outer_dims.checked_add(1)
+                                        .ok_or(BadBinaryFile)?)?;
+    let indices = read_indices(nnz)?;
+    let mut data = Vec::new();
+    for _ in 0..nnz {
+        data.push(N::read_le(reader)?);
+    }
+    let mat = CsMatViewI::new_view(storage, (rows, cols),
+                                   &indptr, &indices, &data);
+    mat.map(|m| m.to_owned()).map_err(|_| BadBinaryFile)
+}
+
 #[cfg(test)]
 mod test {
     use super::{
         read_matrix_market,
+        read_matrix_market_from_bufread,
         write_matrix_market,
+        write_matrix_market_to_writer,
         write_matrix_market_sym,
+        write_binary,
+        read_binary,
         SymmetryMode,
         IoError,
     };
@@ -420,6 +655,39 @@ mod test {
                    &[1., 10.5, 1.5e-02, 6., 2.505e2, -2.8e2, 3.332e1, 1.2e+1]);
     }
 
+    #[test]
+    fn matrix_market_read_from_memory() {
+        let mm = "%%MatrixMarket matrix coordinate real general\n\
+                  % a comment line\n\
+                  \n\
+                  3 4 2\n\
+                  1 4 2.5\n\
+                  3 1 -1\n";
+        let mat = read_matrix_market_from_bufread::<f64, usize, _>(
+            &mut mm.as_bytes()
+        ).unwrap();
+        assert_eq!(mat.rows(), 3);
+        assert_eq!(mat.cols(), 4);
+        assert_eq!(mat.row_inds(), &[0, 2]);
+        assert_eq!(mat.col_inds(), &[3, 0]);
+        assert_eq!(mat.data(), &[2.5, -1.]);
+        let expected = CsMat::new((3, 4),
+                                  vec![0, 1, 1, 2],
+                                  vec![3, 0],
+                                  vec![2.5, -1.]);
+        assert_eq!(mat.to_csr(), expected);
+    }
+
+    #[test]
+    fn matrix_market_read_fail_truncated() {
+        let mm = "%%MatrixMarket matrix coordinate real general\n\
+                  % no size line\n";
+        let res = read_matrix_market_from_bufread::<f64, usize, _>(
+            &mut mm.as_bytes()
+        );
+        assert_eq!(res.unwrap_err(), IoError::BadMatrixMarketFile);
+    }
+
     #[test]
     fn int_matrix_market_read() {
         let path = "data/matrix_market/simple_int.mm";
@@ -468,6 +736,29 @@ mod test {
         assert_eq!(mat, mat3);
     }
 
+    #[test]
+    fn write_read_matrix_market_in_memory() {
+        // | 0 1.5 0 |
+        // | 2 0   3 |
+        let mat = CsMat::new((2, 3),
+                             vec![0, 1, 3],
+                             vec![1, 0, 2],
+                             vec![1.5, 2., 3.]);
+        let mut buf = Vec::new();
+        write_matrix_market_to_writer(&mut buf, &mat).unwrap();
+        let mat2 = read_matrix_market_from_bufread::<f64, usize, _>(
+            &mut &buf[..]
+        ).unwrap();
+        assert_eq!(mat2.to_csr(), mat);
+
+        let mut buf = Vec::new();
+        write_matrix_market_to_writer(&mut buf, &mat.to_csc()).unwrap();
+        let mat3 = read_matrix_market_from_bufread::<f64, usize, _>(
+            &mut &buf[..]
+        ).unwrap();
+        assert_eq!(mat3.to_csr(), mat);
+    }
+
     #[test]
     fn read_write_read_matrix_market_via_csc() {
         let path = "data/matrix_market/simple.mm";
@@ -554,4 +845,67 @@ mod test {
         let mat2 = read_matrix_market::<i32, usize, _>(&save_path).unwrap();
         assert_eq!(mat, mat2.to_csr());
     }
+
+    #[test]
+    fn binary_roundtrip() {
+        use std::io::Cursor;
+        use test_data::{mat1, mat1_csc};
+        let mut cursor = Cursor::new(Vec::new());
+        write_binary(&mut cursor, &mat1()).unwrap();
+        // magic, storage, shape and nnz, indptr, indices, data
+        assert_eq!(cursor.get_ref().len(), 8 + 1 + 24 + 6 * 8 + 7 * 8 + 7 * 8);
+        cursor.set_position(0);
+        let res: CsMat<f64> = read_binary(&mut cursor).unwrap();
+        assert_eq!(res, mat1());
+
+        let mut cursor = Cursor::new(Vec::new());
+        let mat_int = mat1_csc().map(|&x| x as i32);
+        write_binary(&mut cursor, &mat_int).unwrap();
+        cursor.set_position(0);
+        let res: CsMat<i32> = read_binary(&mut cursor).unwrap();
+        assert_eq!(res, mat_int);
+    }
+
+    #[test]
+    fn binary_read_errors() {
+        use test_data::mat1;
+        let mut buf = Vec::new();
+        write_binary(&mut buf, &mat1()).unwrap();
+
+        let mut bad_magic = buf.clone();
+        bad_magic[0] = b'X';
+        let res = read_binary::<f64, usize, _>(&mut &bad_magic[..]);
+        assert_eq!(res.unwrap_err(), IoError::BadBinaryFile);
+
+        // corrupt the first index, making it out of bounds
+        let mut bad_index = buf.clone();
+        bad_index[8 + 1 + 24 + 6 * 8] = 42;
+        let res = read_binary::<f64, usize, _>(&mut &bad_index[..]);
+        assert_eq!(res.unwrap_err(), IoError::BadBinaryFile);
+
+        // corrupt the number of rows, overflowing the indptr length
+        let mut bad_header = buf.clone();
+        for byte in &mut bad_header[9..17] {
+            *byte = 0xff;
+        }
+        let res = read_binary::<f64, usize, _>(&mut &bad_header[..]);
+        assert_eq!(res.unwrap_err(), IoError::BadBinaryFile);
+
+        // truncate the data array
+        let truncated = &buf[..buf.len() - 1];
+        let res = read_binary::<f64, usize, _>(&mut &truncated[..]);
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn binary_read_index_overflow() {
+        // the inner index 69999 cannot be represented as an u16
+        let mat = CsMat::new((1, 70000), vec![0, 1], vec![69999], vec![1.]);
+        let mut buf = Vec::new();
+        write_binary(&mut buf, &mat).unwrap();
+        let res = read_binary::<f64, u16, _>(&mut &buf[..]);
+        assert_eq!(res.unwrap_err(), IoError::BadBinaryFile);
+        let res = read_binary::<f64, u32, _>(&mut &buf[..]).unwrap();
+        assert_eq!(res.indices(), &[69999]);
+    }
 }
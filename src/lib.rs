@@ -11,6 +11,10 @@ It features a sparse matrix type, [**`CsMat`**](struct.CsMatBase.html), and a sp
 - sparse matrix/sparse matrix addition, multiplication.
 - sparse vector/sparse vector addition, dot product.
 - sparse matrix/dense matrix addition, multiplication.
+- conversion from and to [ndarray](https://docs.rs/ndarray)'s dense matrices,
+  using [`to_dense`](struct.CsMatBase.html#method.to_dense),
+  [`csr_from_dense`](fn.csr_from_dense.html) and
+  [`csc_from_dense`](fn.csc_from_dense.html).
 - sparse triangular solves.
 - powerful iteration over the sparse structure, enabling easy extension of the library.
 - matrix construction using the [triplet format](struct.TriMatBase.html),
@@ -18,6 +22,10 @@ It features a sparse matrix type, [**`CsMat`**](struct.CsMatBase.html), and a sp
 - sparse cholesky solver in the separate crate `sprs-ldl`.
 - fully generic integer type for the storage of indices, enabling compact
   representations.
+- optional parallel matrix/dense vector product using rayon, enabled by the
+  `rayon` feature.
+- optional serialization of matrices through serde, enabled by the `serde`
+  feature.
 - planned interoperability with existing sparse solvers such as SuiteSparse.
 
 ## Quick Examples
@@ -64,6 +72,12 @@ extern crate num_traits;
 extern crate num_complex;
 extern crate ndarray;
 #[cfg(test)] extern crate tempdir;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(all(test, feature = "serde"))]
+extern crate serde_json;
+#[cfg(feature = "rayon")]
+extern crate rayon;
 #[cfg(feature = "alga")]
 extern crate alga;
 
@@ -81,6 +95,7 @@ pub type Ix1 = ndarray::Ix1;
 pub type Ix2 = ndarray::Ix2;
 
 pub use indexing::SpIndex;
+pub use num_kinds::Conjugate;
 
 pub use sparse::{
     CsMatBase,
@@ -149,6 +164,7 @@ pub mod vec {
         IntoSparseVecIter,
         NnzOrZip,
         NnzEither,
+        Norm,
     };
 }
 
@@ -156,6 +172,7 @@ pub use sparse::construct::{
     vstack,
     hstack,
     bmat,
+    block_diag,
     csr_from_dense,
     csc_from_dense,
 };
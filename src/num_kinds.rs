@@ -1,5 +1,5 @@
 //! Trait to be able to know at runtime if a generic scalar is an integer, a float
-//! or a complex.
+//! or a complex, and trait for the conjugation of scalars.
 
 use num_complex::{Complex32, Complex64};
 
@@ -62,3 +62,50 @@ macro_rules! complex_prim_kind_impl {
 
 complex_prim_kind_impl!(Complex32);
 complex_prim_kind_impl!(Complex64);
+
+/// Complex conjugation of a scalar.
+///
+/// This trait is implemented for the primitive types, for which the
+/// conjugate is the identity, and for `num_complex`'s complex types. It can
+/// be implemented for other complex types to enable eg
+/// [`CsMat::conj`](struct.CsMatBase.html#method.conj).
+pub trait Conjugate {
+    /// The complex conjugate of this scalar
+    fn conj(&self) -> Self;
+}
+
+macro_rules! real_conjugate_impl {
+    ($prim: ty) => (
+        impl Conjugate for $prim {
+            fn conj(&self) -> $prim {
+                *self
+            }
+        }
+    )
+}
+
+real_conjugate_impl!(i8);
+real_conjugate_impl!(u8);
+real_conjugate_impl!(i16);
+real_conjugate_impl!(u16);
+real_conjugate_impl!(i32);
+real_conjugate_impl!(u32);
+real_conjugate_impl!(i64);
+real_conjugate_impl!(u64);
+real_conjugate_impl!(isize);
+real_conjugate_impl!(usize);
+real_conjugate_impl!(f32);
+real_conjugate_impl!(f64);
+
+macro_rules! complex_conjugate_impl {
+    ($prim: ty) => (
+        impl Conjugate for $prim {
+            fn conj(&self) -> $prim {
+                <$prim>::conj(self)
+            }
+        }
+    )
+}
+
+complex_conjugate_impl!(Complex32);
+complex_conjugate_impl!(Complex64);
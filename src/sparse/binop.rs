@@ -358,6 +358,50 @@ mod test {
         assert_eq!(c, &a + &b);
     }
 
+    #[test]
+    fn add_partial_overlap() {
+        // | 1 0 2 0 |   | 0 0 1 3 |   | 1 0 3 3 |
+        // | 0 4 0 0 | + | 5 0 0 0 | = | 5 4 0 0 |
+        // | 0 0 0 6 |   | 0 0 0 1 |   | 0 0 0 7 |
+        let a = CsMat::new((3, 4),
+                           vec![0, 2, 3, 4],
+                           vec![0, 2, 1, 3],
+                           vec![1., 2., 4., 6.]);
+        let b = CsMat::new((3, 4),
+                           vec![0, 2, 3, 4],
+                           vec![2, 3, 0, 3],
+                           vec![1., 3., 5., 1.]);
+        let c = &a + &b;
+        assert_eq!(c.nnz(), 6);
+        assert_eq!(c.indptr(), &[0, 3, 5, 6]);
+        assert_eq!(c.indices(), &[0, 2, 3, 0, 1, 3]);
+        assert_eq!(c.data(), &[1., 3., 3., 5., 4., 7.]);
+        assert!(c.check_compressed_structure().is_ok());
+    }
+
+    #[test]
+    fn add_sub_storage_conversion() {
+        let a = mat1();
+        let b = mat2().to_csc();
+        let c = &a + &b;
+        assert!(c.is_csr());
+        assert_eq!(c, mat1_plus_mat2());
+        let c = &a - &b;
+        assert!(c.is_csr());
+        assert_eq!(c, mat1_minus_mat2());
+        let c = &b + &a;
+        assert!(c.is_csc());
+        assert_eq!(c, mat1_plus_mat2().to_csc());
+    }
+
+    #[test]
+    #[should_panic]
+    fn add_shape_mismatch() {
+        let a: CsMat<f64> = CsMat::eye(3);
+        let b: CsMat<f64> = CsMat::eye(4);
+        let _c = &a + &b;
+    }
+
     #[test]
     fn test_sub1() {
         let a = mat1();
@@ -371,6 +415,24 @@ mod test {
         assert_eq!(c, c_true);
     }
 
+    #[test]
+    fn sub_self() {
+        let a = mat1();
+        let c = &a - &a;
+        assert_eq!(c.shape(), a.shape());
+        assert_eq!(c.nnz(), 0);
+        assert_eq!(c, CsMat::zero(a.shape()));
+
+        // | 1 0 2 |   | 1 3 0 |   | 0 -3 2 |
+        // | 0 4 0 | - | 0 0 0 | = | 0  4 0 |
+        let b = CsMat::new((2, 3), vec![0, 2, 3], vec![0, 2, 1],
+                           vec![1., 2., 4.]);
+        let d = CsMat::new((2, 3), vec![0, 2, 2], vec![0, 1], vec![1., 3.]);
+        let expected = CsMat::new((2, 3), vec![0, 2, 3], vec![1, 2, 1],
+                                  vec![-3., 2., 4.]);
+        assert_eq!(&b - &d, expected);
+    }
+
     #[test]
     fn test_mul1() {
         let a = mat1();
@@ -43,6 +43,14 @@ where N: 'a + Clone,
 }
 
 /// Construct a sparse matrix by vertically stacking other matrices
+///
+/// The result is a CSR matrix. Stacking CSR matrices is cheap, while CSC
+/// matrices are first converted to CSR.
+///
+/// # Panics
+///
+/// - if `mats` is empty
+/// - if the matrices do not have the same number of columns
 pub fn vstack<'a, N, MatArray>(mats: &MatArray) -> CsMat<N>
 where N: 'a + Clone + Default,
       MatArray: AsRef<[CsMatView<'a, N>]> {
@@ -57,6 +65,14 @@ where N: 'a + Clone + Default,
 }
 
 /// Construct a sparse matrix by horizontally stacking other matrices
+///
+/// The result is a CSC matrix. Stacking CSC matrices is cheap, while CSR
+/// matrices are first converted to CSC.
+///
+/// # Panics
+///
+/// - if `mats` is empty
+/// - if the matrices do not have the same number of rows
 pub fn hstack<'a, N, MatArray>(mats: &MatArray) -> CsMat<N>
 where N: 'a + Clone + Default,
       MatArray: AsRef<[CsMatView<'a, N>]> {
@@ -134,6 +150,71 @@ where N: 'a + Clone + Default,
     vstack(&borrows)
 }
 
+/// Construct a block diagonal sparse matrix, ie a matrix having the given
+/// matrices as diagonal blocks, and zeros elsewhere.
+///
+/// All the matrices should have the same storage, which is the storage of
+/// the resulting matrix.
+///
+/// # Panics
+///
+/// - if `mats` is empty
+/// - if the matrices do not have the same storage
+///
+/// # Examples
+/// ```
+/// use sprs::CsMat;
+/// let a = CsMat::<f64>::eye(2);
+/// let b = CsMat::<f64>::eye(3);
+/// let c = sprs::block_diag(&[a.view(), b.view()]);
+/// assert_eq!(c, CsMat::eye(5));
+/// ```
+pub fn block_diag<'a, N, MatArray>(mats: &MatArray) -> CsMat<N>
+where N: 'a + Clone,
+      MatArray: AsRef<[CsMatView<'a, N>]> {
+    let mats = mats.as_ref();
+    if mats.len() == 0 {
+        panic!("Empty stacking list");
+    }
+    let storage_type = mats[0].storage();
+    if ! mats.iter().all(|x| x.storage() == storage_type) {
+        panic!("Storage mismatch");
+    }
+
+    let outer_dim = mats.iter().map(|x| x.outer_dims()).fold(0, |x, y| x + y);
+    let inner_dim = mats.iter().map(|x| x.inner_dims()).fold(0, |x, y| x + y);
+    let nnz = mats.iter().map(|x| x.nnz()).fold(0, |x, y| x + y);
+
+    let mut indptr = Vec::with_capacity(outer_dim + 1);
+    let mut indices = Vec::with_capacity(nnz);
+    let mut data = Vec::with_capacity(nnz);
+    indptr.push(0);
+    let mut inner_offset = 0;
+    for mat in mats {
+        for vec in mat.outer_iterator() {
+            for (ind, val) in vec.iter() {
+                indices.push(inner_offset + ind);
+                data.push(val.clone());
+            }
+            indptr.push(indices.len());
+        }
+        inner_offset += mat.inner_dims();
+    }
+
+    let (nrows, ncols) = match storage_type {
+        CompressedStorage::CSR => (outer_dim, inner_dim),
+        CompressedStorage::CSC => (inner_dim, outer_dim),
+    };
+    CsMat {
+        storage: storage_type,
+        nrows: nrows,
+        ncols: ncols,
+        indptr: indptr,
+        indices: indices,
+        data: data,
+    }
+}
+
 /// Create a CSR matrix from a dense matrix, ignoring elements
 /// lower than `epsilon`.
 ///
@@ -256,6 +337,34 @@ mod test {
         assert_eq!(res, expected);
     }
 
+    #[test]
+    fn hstack_with_conversion() {
+        // | 1 0 | 2 |
+        // | 0 3 | 0 |
+        let a = CsMat::new((2, 2), vec![0, 1, 2], vec![0, 1], vec![1., 3.]);
+        let b = CsMat::new((2, 1), vec![0, 1, 1], vec![0], vec![2.]);
+        let res = super::hstack(&[a.view(), b.view()]);
+        assert!(res.is_csc());
+        assert_eq!(res.to_dense(), arr2(&[[1., 0., 2.],
+                                          [0., 3., 0.]]));
+    }
+
+    #[test]
+    #[should_panic]
+    fn vstack_fail_cols() {
+        let a = CsMat::<f64>::eye(2);
+        let b = CsMat::<f64>::eye(3);
+        super::vstack(&[a.view(), b.view()]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn hstack_fail_rows() {
+        let a = CsMat::<f64>::eye_csc(2);
+        let b = CsMat::<f64>::eye_csc(3);
+        super::hstack(&[a.view(), b.view()]);
+    }
+
     #[test]
     #[should_panic]
     fn bmat_fail_shapes() {
@@ -370,4 +479,56 @@ mod test {
 
         assert_eq!(m_sparse, expected_output);
     }
+
+    #[test]
+    fn dense_roundtrip() {
+        let data = vec![0., 1., 0., 2.,
+                        0., 0., 0., 0.,
+                        3., 0., 4., 5.];
+        let m = Array::from_shape_vec((3, 4), data).unwrap();
+        let m_csr = super::csr_from_dense(m.view(), 0.);
+        assert_eq!(m_csr.nnz(), 5);
+        assert_eq!(m_csr.to_dense(), m);
+        let m_csc = super::csc_from_dense(m.view(), 0.);
+        assert_eq!(m_csc.nnz(), 5);
+        assert_eq!(m_csc.to_dense(), m);
+        assert_eq!(m_csc, m_csr.to_csc());
+    }
+
+    #[test]
+    fn block_diag() {
+        // | 1 2 0 |
+        // | 0 3 0 |
+        // | 0 0 4 |
+        let a = CsMat::new((2, 2), vec![0, 2, 3], vec![0, 1, 1],
+                           vec![1., 2., 3.]);
+        let b = CsMat::new((1, 1), vec![0, 1], vec![0], vec![4.]);
+        let expected = arr2(&[[1., 2., 0.],
+                              [0., 3., 0.],
+                              [0., 0., 4.]]);
+        let res = super::block_diag(&[a.view(), b.view()]);
+        assert_eq!(res.shape(), (3, 3));
+        assert!(res.is_csr());
+        assert_eq!(res.to_dense(), expected);
+        res.check_compressed_structure().unwrap();
+
+        let (a, b) = (a.to_csc(), b.to_csc());
+        let res = super::block_diag(&[a.view(), b.view()]);
+        assert!(res.is_csc());
+        assert_eq!(res.to_dense(), expected);
+
+        // non square blocks
+        let c = CsMat::new((1, 2), vec![0, 1], vec![1], vec![5.]);
+        let res = super::block_diag(&[c.view(), c.view()]);
+        assert_eq!(res.to_dense(), arr2(&[[0., 5., 0., 0.],
+                                          [0., 0., 0., 5.]]));
+    }
+
+    #[test]
+    #[should_panic]
+    fn block_diag_fail_storage() {
+        let a = CsMat::<f64>::eye(2);
+        let b = CsMat::<f64>::eye_csc(2);
+        super::block_diag(&[a.view(), b.view()]);
+    }
 }
@@ -10,9 +10,11 @@
 
 use std::default::Default;
 use std::slice::{self, Iter, Windows};
-use std::ops::{Deref, DerefMut, Add, Sub, Mul, Range, Index, IndexMut};
+use std::ops::{Deref, DerefMut, Add, Sub, Mul, Neg, Range, Index, IndexMut};
 use std::mem;
-use num_traits::{Num, Zero};
+use std::cmp;
+use std::fmt;
+use num_traits::{Float, Num, Signed, Zero};
 use std::iter::{Enumerate, Zip};
 
 use ndarray::{self, ArrayBase, Array, ShapeBuilder};
@@ -23,16 +25,22 @@ use array_backend::Array2;
 
 use sparse::prelude::*;
 use sparse::permutation::PermViewI;
-use sparse::vec;
+use sparse::vec::{self, SparseIterTools};
+use sparse::vec::NnzEither::{Left, Right, Both};
 use sparse::compressed::SpMatView;
 use sparse::binop;
+use sparse::symmetric;
 use sparse::prod;
 use sparse::utils;
 use errors::SprsError;
 use sparse::to_dense::assign_to_dense;
+use num_kinds::Conjugate;
+#[cfg(feature = "serde")]
+use serde::{Serialize, Deserialize};
 
 /// Describe the storage of a CsMat
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum CompressedStorage {
     /// Compressed row storage
     CSR,
@@ -248,6 +256,12 @@ for OuterIterator<'iter, N, I> {
     }
 }
 
+/// Iterator on the non-zero elements of a compressed matrix, yielding
+/// the values along with their `(row, col)` location.
+///
+/// The elements are yielded in storage order: row by row for a CSR matrix,
+/// and column by column for a CSC matrix. Inside an outer dimension, the
+/// elements are yielded by increasing inner index.
 pub struct CsIter<'a, N: 'a, I: 'a>
 {
     storage: CompressedStorage,
@@ -291,6 +305,76 @@ where I: SpIndex,
     }
 }
 
+/// Iterator consuming an owned compressed matrix, yielding its non-zero
+/// elements as `(row, col, value)` triplets. The values are moved out of
+/// the matrix.
+///
+/// The elements are yielded in storage order, as for `CsIter`.
+pub struct CsIntoIter<N, I> {
+    storage: CompressedStorage,
+    cur_outer: usize,
+    indptr: Vec<I>,
+    inner_iter: Enumerate<Zip<::std::vec::IntoIter<I>,
+                              ::std::vec::IntoIter<N>>>,
+}
+
+impl<N, I: SpIndex> Iterator for CsIntoIter<N, I> {
+    type Item = (usize, usize, N);
+    fn next(&mut self) -> Option<<Self as Iterator>::Item> {
+        self.inner_iter.next().map(|(nnz_index, (inner_ind, val))| {
+            // skip the outer dimensions that have been exhausted,
+            // possibly several if there are empty outer dimensions
+            while nnz_index == self.indptr[self.cur_outer + 1].index() {
+                self.cur_outer += 1;
+            }
+            let inner = inner_ind.index();
+            match self.storage {
+                CSR => (self.cur_outer, inner, val),
+                CSC => (inner, self.cur_outer, val),
+            }
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner_iter.size_hint()
+    }
+}
+
+/// A cursor over an outer dimension of a matrix, giving fast access to
+/// its elements when they are queried in increasing inner index order.
+///
+/// The cursor remembers the position of the last query, so that a sequence
+/// of increasing queries costs `O(nnz)` for the whole outer dimension,
+/// instead of a binary search for each query.
+pub struct OuterCursor<'a, N: 'a, I: 'a> {
+    indices: &'a [I],
+    data: &'a [N],
+    pos: usize,
+}
+
+impl<'a, N: 'a, I: 'a + SpIndex> OuterCursor<'a, N, I> {
+    /// Get the element at the inner index `inner`, if it is stored.
+    ///
+    /// Queries should be made in increasing inner index order to be fast.
+    /// Seeking an index lower than the previous query is supported, but
+    /// restarts the search from the beginning of the outer dimension.
+    pub fn seek(&mut self, inner: usize) -> Option<&'a N> {
+        if self.pos > 0 && self.indices[self.pos - 1].index() >= inner {
+            self.pos = 0;
+        }
+        while self.pos < self.indices.len()
+            && self.indices[self.pos].index() < inner {
+            self.pos += 1;
+        }
+        if self.pos < self.indices.len()
+            && self.indices[self.pos].index() == inner {
+            Some(&self.data[self.pos])
+        } else {
+            None
+        }
+    }
+}
+
 /// # Constructor methods for owned sparse matrices
 impl<N, I: SpIndex> CsMatBase<N, I, Vec<I>, Vec<I>, Vec<N>> {
 
@@ -347,6 +431,32 @@ impl<N, I: SpIndex> CsMatBase<N, I, Vec<I>, Vec<I>, Vec<N>> {
             data: data,
         }
     }
+
+    /// Create a square CSR matrix having the given values on its diagonal.
+    ///
+    /// All the diagonal values are stored, even if they are zero. This is
+    /// the inverse of `diag()`.
+    ///
+    /// ```rust
+    /// use sprs::CsMat;
+    /// let mat = CsMat::from_diag(&[1., 2., 3.]);
+    /// assert_eq!(mat.get(1, 1), Some(&2.));
+    /// assert_eq!(mat.get(0, 1), None);
+    /// ```
+    pub fn from_diag(diag: &[N]) -> CsMatI<N, I>
+    where N: Clone
+    {
+        let n = diag.len();
+        CsMatI {
+            storage: CSR,
+            nrows: n,
+            ncols: n,
+            indptr: (0..n+1).map(I::from_usize).collect(),
+            indices: (0..n).map(I::from_usize).collect(),
+            data: diag.to_vec(),
+        }
+    }
+
     /// Create an empty CsMat for building purposes
     pub fn empty(storage: CompressedStorage,
                  inner_size: usize
@@ -379,6 +489,37 @@ impl<N, I: SpIndex> CsMatBase<N, I, Vec<I>, Vec<I>, Vec<N>> {
         }
     }
 
+    /// Create a new CSC CsMat representing the zero matrix.
+    /// Hence it has no non-zero elements.
+    pub fn zero_csc(shape: Shape) -> CsMat<N> {
+        let (rows, cols) = shape;
+        CsMat {
+            storage: CSC,
+            nrows: rows,
+            ncols: cols,
+            indptr: vec![0; cols + 1],
+            indices: Vec::new(),
+            data: Vec::new(),
+        }
+    }
+
+    /// Create an empty CsMat for building purposes, reserving the storage
+    /// for `outer_dims` outer dimensions and `nnz` non zero elements.
+    ///
+    /// The reserved sizes are only hints to avoid reallocations while
+    /// building the matrix with eg `append_outer` or `append_outer_csvec`,
+    /// the built matrix can have more or fewer elements.
+    pub fn with_capacity(storage: CompressedStorage,
+                         inner_size: usize,
+                         outer_dims: usize,
+                         nnz: usize
+                        ) -> CsMatI<N, I> {
+        let mut mat = CsMatI::empty(storage, inner_size);
+        mat.reserve_outer_dim_exact(outer_dims);
+        mat.reserve_nnz_exact(nnz);
+        mat
+    }
+
     /// Reserve the storage for the given additional number of nonzero data
     pub fn reserve_outer_dim(&mut self, outer_dim_additional: usize) {
         self.indptr.reserve(outer_dim_additional);
@@ -401,6 +542,17 @@ impl<N, I: SpIndex> CsMatBase<N, I, Vec<I>, Vec<I>, Vec<N>> {
         self.data.reserve_exact(nnz_lim);
     }
 
+    /// Shrink the capacity of the underlying storage as much as possible,
+    /// eg after removing elements with `prune`.
+    ///
+    /// Storage for a batch of insertions can be reserved beforehand using
+    /// `reserve_nnz`.
+    pub fn shrink_to_fit(&mut self) {
+        self.indptr.shrink_to_fit();
+        self.indices.shrink_to_fit();
+        self.data.shrink_to_fit();
+    }
+
     /// Create an owned CSR matrix from moved data.
     ///
     /// An owned CSC matrix can be created with `new_csc()`.
@@ -459,6 +611,125 @@ impl<N, I: SpIndex> CsMatBase<N, I, Vec<I>, Vec<I>, Vec<N>> {
         CsMatI::new_(CSC, shape, indptr, indices, data).unwrap()
     }
 
+    /// Create an owned matrix from triplets of row indices, column indices
+    /// and values, with the requested storage.
+    ///
+    /// The triplets can be given in any order, and a location can appear
+    /// several times, in which case the corresponding values are summed.
+    ///
+    /// # Errors
+    ///
+    /// `OutOfBoundsIndex` if a row or column index does not fit in `shape`.
+    ///
+    /// # Panics
+    ///
+    /// - if `row_inds`, `col_inds` and `data` don't have the same length.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sprs::{CsMat, CSR};
+    /// // | 1 0 |
+    /// // | 0 5 |
+    /// let mat = CsMat::from_triplets((2, 2), CSR,
+    ///                                &[1, 0, 1], &[1, 0, 1], &[2., 1., 3.])
+    ///                 .unwrap();
+    /// assert_eq!(mat, CsMat::new((2, 2), vec![0, 1, 2],
+    ///                            vec![0, 1], vec![1., 5.]));
+    /// ```
+    pub fn from_triplets(shape: Shape,
+                         storage: CompressedStorage,
+                         row_inds: &[I],
+                         col_inds: &[I],
+                         data: &[N]
+                        ) -> Result<CsMatI<N, I>, SprsError>
+    where N: Clone + Num
+    {
+        let (rows, cols) = shape;
+        assert!(row_inds.len() == data.len() && col_inds.len() == data.len(),
+                "all inputs should have the same length");
+        if row_inds.iter().any(|&i| i.index() >= rows)
+            || col_inds.iter().any(|&j| j.index() >= cols) {
+            return Err(SprsError::OutOfBoundsIndex);
+        }
+        let nnz = data.len();
+        match storage {
+            CSC => Ok(TriMatIter::new(shape, nnz, row_inds.iter(),
+                                      col_inds.iter(), data.iter())
+                          .into_csc()),
+            // a CSR matrix is the transpose of the CSC matrix built from
+            // the transposed triplets
+            CSR => Ok(TriMatIter::new((cols, rows), nnz, col_inds.iter(),
+                                      row_inds.iter(), data.iter())
+                          .into_csc()
+                          .transpose_into()),
+        }
+    }
+
+    /// Create an owned matrix from an iterator yielding `(row, col, value)`
+    /// triplets in the order of the requested storage (ie row by row for
+    /// a CSR matrix, with increasing columns in each row).
+    ///
+    /// The elements are directly streamed into the compressed structure,
+    /// without buffering nor sorting.
+    ///
+    /// # Errors
+    ///
+    /// - `OutOfBoundsIndex` if a row or column index does not fit in
+    ///   `shape`.
+    /// - `NonSortedIndices` if the triplets are not yielded in storage
+    ///   order, or if a location is yielded several times.
+    pub fn from_sorted_iter<It>(shape: Shape,
+                                storage: CompressedStorage,
+                                iter: It
+                               ) -> Result<CsMatI<N, I>, SprsError>
+    where It: IntoIterator<Item=(usize, usize, N)>
+    {
+        let (rows, cols) = shape;
+        let outer_dims = outer_dimension(storage, rows, cols);
+        let mut indptr = Vec::with_capacity(outer_dims + 1);
+        let mut indices = Vec::new();
+        let mut data = Vec::new();
+        indptr.push(I::zero());
+        let mut last_inner = None;
+        for (row, col, val) in iter {
+            if row >= rows || col >= cols {
+                return Err(SprsError::OutOfBoundsIndex);
+            }
+            let (outer, inner) = match storage {
+                CSR => (row, col),
+                CSC => (col, row),
+            };
+            let cur_outer = indptr.len() - 1;
+            if outer < cur_outer {
+                return Err(SprsError::NonSortedIndices);
+            }
+            if outer > cur_outer {
+                let nnz = I::from_usize(indices.len());
+                indptr.resize(outer + 1, nnz);
+                last_inner = None;
+            }
+            if let Some(last) = last_inner {
+                if inner <= last {
+                    return Err(SprsError::NonSortedIndices);
+                }
+            }
+            last_inner = Some(inner);
+            indices.push(I::from_usize(inner));
+            data.push(val);
+        }
+        let nnz = I::from_usize(indices.len());
+        indptr.resize(outer_dims + 1, nnz);
+        Ok(CsMatI {
+            storage: storage,
+            nrows: rows,
+            ncols: cols,
+            indptr: indptr,
+            indices: indices,
+            data: data,
+        })
+    }
+
     fn new_(storage: CompressedStorage,
             shape: Shape,
             indptr : Vec<I>,
@@ -515,6 +786,27 @@ impl<N, I: SpIndex> CsMatBase<N, I, Vec<I>, Vec<I>, Vec<N>> {
     }
 
     /// Append an outer dim to an existing matrix, provided by a sparse vector
+    ///
+    /// This enables building a matrix one outer dimension at a time, without
+    /// having to collect all its elements first. Checking the structure of
+    /// the appended vectors is done by `CsVecView::new_view`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sprs::{CsMat, CsVecView, CSR};
+    /// # fn main() { build().unwrap(); }
+    /// # fn build() -> Result<(), sprs::errors::SprsError> {
+    /// let mut mat = CsMat::empty(CSR, 4);
+    /// let row = CsVecView::new_view(4, &[0, 2], &[1., 2.])?;
+    /// mat = mat.append_outer_csvec(row);
+    /// mat = mat.append_outer_csvec(CsVecView::new_view(4, &[], &[])?);
+    /// mat = mat.append_outer_csvec(CsVecView::new_view(4, &[3], &[3.])?);
+    /// assert_eq!(mat.shape(), (3, 4));
+    /// assert_eq!(mat.indptr(), &[0, 2, 2, 3]);
+    /// # Ok(())
+    /// # }
+    /// ```
     pub fn append_outer_csvec(mut self, vec: CsVecBase<&[I], &[N]>) -> Self
     where N: Clone
     {
@@ -532,11 +824,56 @@ impl<N, I: SpIndex> CsMatBase<N, I, Vec<I>, Vec<I>, Vec<N>> {
         self
     }
 
+    /// Append the sparse vectors yielded by `vecs` as new outer dimensions
+    /// of this matrix, eg as new rows of a CSR matrix.
+    ///
+    /// This enables streaming the assembly of a matrix when its outer
+    /// dimensions become available one at a time.
+    ///
+    /// # Errors
+    ///
+    /// - `IncompatibleDimensions` if a vector's dimension differs from the
+    ///   inner dimension of this matrix.
+    /// - `OutOfBoundsIndex` if a vector has an index greater or equal to
+    ///   the inner dimension of this matrix.
+    /// - `NonSortedIndices` if a vector's indices are not sorted.
+    ///
+    /// The vectors are checked one at a time, so the vectors yielded before
+    /// an invalid vector are still appended when an error is returned.
+    pub fn extend_outer<It>(&mut self, vecs: It) -> Result<(), SprsError>
+    where It: IntoIterator<Item=CsVecI<N, I>>
+    {
+        let inner_dims = self.inner_dims();
+        for vec in vecs {
+            let CsVecBase { dim, indices, data } = vec;
+            if dim != inner_dims {
+                return Err(SprsError::IncompatibleDimensions);
+            }
+            if indices.iter().any(|ind| ind.index() >= inner_dims) {
+                return Err(SprsError::OutOfBoundsIndex);
+            }
+            if indices.windows(2).any(|w| w[0] >= w[1]) {
+                return Err(SprsError::NonSortedIndices);
+            }
+            self.indices.extend(indices);
+            self.data.extend(data);
+            match self.storage {
+                CSR => self.nrows += 1,
+                CSC => self.ncols += 1
+            }
+            self.indptr.push(I::from_usize(self.indices.len()));
+        }
+        Ok(())
+    }
+
     /// Insert an element in the matrix. If the element is already present,
     /// its value is overwritten.
     ///
     /// Warning: this is not an efficient operation, as it requires
-    /// a non-constant lookup followed by two `Vec` insertions.
+    /// a non-constant lookup followed by two `Vec` insertions. Inserting
+    /// a new element is thus `O(nnz)` in the worst case, as the following
+    /// elements need to be shifted, while updating an existing element
+    /// only costs the lookup.
     ///
     /// The insertion will be efficient, however, if the elements are inserted
     /// according to the matrix's order, eg following the row order for a CSR
@@ -595,6 +932,115 @@ impl<N, I: SpIndex> CsMatBase<N, I, Vec<I>, Vec<I>, Vec<N>> {
         }
     }
 
+    /// Consume this matrix and return its components, as a tuple
+    /// `(storage, nrows, ncols, indptr, indices, data)`.
+    ///
+    /// This enables transferring the ownership of the underlying arrays,
+    /// for instance to another library, without copying them.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sprs::{CsMat, CSR};
+    /// let eye: CsMat<f64> = CsMat::eye(2);
+    /// let (storage, nrows, ncols, indptr, indices, data) = eye.into_raw();
+    /// assert_eq!(storage, CSR);
+    /// assert_eq!((nrows, ncols), (2, 2));
+    /// assert_eq!(indptr, vec![0, 1, 2]);
+    /// assert_eq!(indices, vec![0, 1]);
+    /// assert_eq!(data, vec![1., 1.]);
+    /// ```
+    pub fn into_raw(self) -> (CompressedStorage, usize, usize,
+                              Vec<I>, Vec<I>, Vec<N>) {
+        (self.storage, self.nrows, self.ncols,
+         self.indptr, self.indices, self.data)
+    }
+
+    /// Convert this square matrix to the other storage in place, ie a
+    /// CSR matrix becomes a CSC matrix representing the same mathematical
+    /// matrix, and vice versa.
+    ///
+    /// Contrary to `to_other_storage`, the `indptr`, `indices` and `data`
+    /// arrays are reused, which lowers the peak memory usage. Only a
+    /// temporary array of `nnz` positions is allocated.
+    ///
+    /// This is not to be confused with `transpose_mut`, which only changes
+    /// the storage flag, and thus changes the mathematical matrix.
+    ///
+    /// # Panics
+    ///
+    /// - if the matrix is not square
+    pub fn to_other_storage_inplace(&mut self) {
+        assert_eq!(self.nrows, self.ncols, "Non square matrix");
+        // first store the outer dimension of each element
+        let mut buf = vec![0; self.nnz()];
+        for (outer, range) in self.indptr.windows(2).enumerate() {
+            for outer_ind in &mut buf[range[0].index()..range[1].index()] {
+                *outer_ind = outer;
+            }
+        }
+        // indptr is then reused to count the elements of each new outer
+        // dimension, and converted so that indptr[i + 1] is the start of
+        // the new outer dimension i
+        for ptr in self.indptr.iter_mut() {
+            *ptr = I::zero();
+        }
+        for ind in self.indices.iter() {
+            self.indptr[ind.index() + 1] += I::one();
+        }
+        let mut start = I::zero();
+        for ptr in self.indptr.iter_mut().skip(1) {
+            let count = *ptr;
+            *ptr = start;
+            start += count;
+        }
+        // indptr[i + 1] serves as the cursor in the new outer dimension i,
+        // ending as its end. The buffer now holds the destination of each
+        // element, while its index is replaced by its new inner index.
+        for (ind, pos) in self.indices.iter_mut().zip(buf.iter_mut()) {
+            let cursor = &mut self.indptr[ind.index() + 1];
+            *ind = I::from_usize(*pos);
+            *pos = cursor.index();
+            *cursor += I::one();
+        }
+        // apply the permutation by following its cycles
+        for k in 0..buf.len() {
+            while buf[k] != k {
+                let dest = buf[k];
+                self.indices.swap(k, dest);
+                self.data.swap(k, dest);
+                buf.swap(k, dest);
+            }
+        }
+        self.storage = self.storage.other_storage();
+    }
+
+    /// Remove the explicit zeros stored in this matrix.
+    ///
+    /// Operations such as additions can leave explicitly stored zeros in
+    /// the non-zero pattern of a matrix. This method removes them in place,
+    /// leaving the represented matrix unchanged.
+    pub fn prune(&mut self)
+    where N: Zero + PartialEq
+    {
+        let mut nnz = 0;
+        let mut start = 0;
+        for outer in 0..self.outer_dims() {
+            let stop = self.indptr[outer + 1].index();
+            for k in start..stop {
+                if self.data[k] != N::zero() {
+                    self.indices.swap(nnz, k);
+                    self.data.swap(nnz, k);
+                    nnz += 1;
+                }
+            }
+            start = stop;
+            self.indptr[outer + 1] = I::from_usize(nnz);
+        }
+        self.indices.truncate(nnz);
+        self.data.truncate(nnz);
+    }
+
     fn set_outer_dims(&mut self, outer_dims: usize) {
         match self.storage() {
             CSR => self.nrows = outer_dims,
@@ -641,6 +1087,10 @@ impl<'a, N:'a, I: 'a + SpIndex> CsMatBase<N, I, &'a [I], &'a [I], &'a [N]> {
     /// that properties guaranteed by check_compressed_structure are enforced.
     /// For instance, non out-of-bounds indices can be relied upon to
     /// perform unchecked slice access.
+    ///
+    /// When the validity of the data is not known, calling
+    /// `check_compressed_structure` on the resulting view reports the
+    /// violated property as a `SprsError`.
     pub unsafe fn new_view_raw(
         storage: CompressedStorage, shape: Shape,
         indptr : *const I, indices : *const I, data : *const N
@@ -689,6 +1139,11 @@ impl<'a, N:'a, I: 'a + SpIndex> CsMatBase<N, I, &'a [I], &'a [I], &'a [N]> {
     /// Get an iterator that yields the non-zero locations and values stored in
     /// this matrix, in the fastest iteration order.
     ///
+    /// The iteration order follows the storage: for a CSR matrix the
+    /// elements are yielded in row-major order, and for a CSC matrix in
+    /// column-major order. The yielded locations are always `(row, col)`,
+    /// whatever the storage.
+    ///
     /// This method will yield the correct lifetime for iterating over a sparse
     /// matrix view.
     pub fn iter_rbr(&self) -> CsIter<'a, N, I> {
@@ -740,6 +1195,83 @@ where I: SpIndex,
         self.indptr.last().unwrap().index()
     }
 
+    /// The density of this matrix, ie the ratio of its number of non-zero
+    /// elements over its total number of elements.
+    ///
+    /// An empty matrix has a density of 0.
+    pub fn density(&self) -> f64 {
+        let size = self.nrows as f64 * self.ncols as f64;
+        if size == 0. {
+            return 0.;
+        }
+        self.nnz() as f64 / size
+    }
+
+    /// The number of non-zero elements stored in the outer dimension `i`
+    /// (eg the `i`-th row for a CSR matrix).
+    ///
+    /// # Panics
+    ///
+    /// - if `i >= self.outer_dims()`
+    pub fn nnz_in_outer(&self, i: usize) -> usize {
+        assert!(i < self.outer_dims(), "Out of bounds outer index");
+        self.indptr[i + 1].index() - self.indptr[i].index()
+    }
+
+    /// Find the outer dimension holding the most non-zero elements (eg the
+    /// row with the highest degree for the adjacency matrix of a graph),
+    /// returning its index and its number of non-zeros.
+    ///
+    /// If several outer dimensions hold the maximal number of non-zeros,
+    /// the first one is returned. Returns `None` if the matrix has no
+    /// stored element.
+    pub fn densest_outer(&self) -> Option<(usize, usize)> {
+        let mut res = None;
+        let mut max_nnz = 0;
+        for (i, w) in self.indptr.windows(2).enumerate() {
+            let nnz = w[1].index() - w[0].index();
+            if nnz > max_nnz {
+                max_nnz = nnz;
+                res = Some((i, nnz));
+            }
+        }
+        res
+    }
+
+    /// Compute the histogram of the number of non-zeros per outer
+    /// dimension: the `k`-th element of the result is the number of outer
+    /// dimensions holding exactly `k` non-zeros.
+    ///
+    /// For the adjacency matrix of a graph stored as CSR, this is the
+    /// out-degree distribution of the graph. The length of the result is
+    /// one more than the maximum number of non-zeros in an outer dimension,
+    /// or zero if the matrix has no outer dimension.
+    pub fn outer_nnz_histogram(&self) -> Vec<usize> {
+        let mut res = Vec::new();
+        for w in self.indptr.windows(2) {
+            let nnz = w[1].index() - w[0].index();
+            if nnz >= res.len() {
+                res.resize(nnz + 1, 0);
+            }
+            res[nnz] += 1;
+        }
+        res
+    }
+
+    /// Count the non-zero elements stored in each inner dimension (eg
+    /// in each column for a CSR matrix), with a single pass over the
+    /// indices.
+    ///
+    /// These counts are what is needed to build the `indptr` of the matrix
+    /// in the other storage.
+    pub fn inner_nnz_counts(&self) -> Vec<usize> {
+        let mut counts = vec![0; self.inner_dims()];
+        for ind in self.indices.iter() {
+            counts[ind.index()] += 1;
+        }
+        counts
+    }
+
     /// Number of outer dimensions, that ie equal to self.rows() for a CSR
     /// matrix, and equal to self.cols() for a CSC matrix
     pub fn outer_dims(&self) -> usize {
@@ -769,11 +1301,36 @@ where I: SpIndex,
         }
     }
 
+    /// Access the element located at row i and column j, distinguishing
+    /// between out of bounds locations and locations without a non-zero
+    /// element.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SprsError::OutOfBoundsIndex` if `i >= self.rows()` or
+    /// `j >= self.cols()`. Otherwise, returns `Ok(None)` if there is no
+    /// non-zero element at this location.
+    pub fn try_get(&self, i: usize, j: usize)
+        -> Result<Option<&N>, SprsError>
+    {
+        if i >= self.nrows || j >= self.ncols {
+            return Err(SprsError::OutOfBoundsIndex);
+        }
+        Ok(self.get(i, j))
+    }
+
     /// The array of offsets in the indices() and data() slices.
     /// The elements of the slice at outer dimension i
     /// are available between the elements indptr[i] and indptr[i+1]
     /// in the indices() and data() slices.
     ///
+    /// The interpretation of these arrays depends on the storage of the
+    /// matrix: for a CSR matrix, the outer dimension is the row and
+    /// indices() holds column indices, while for a CSC matrix the outer
+    /// dimension is the column and indices() holds row indices. This makes
+    /// them suitable for passing to external libraries expecting raw
+    /// compressed arrays.
+    ///
     /// # Example
     ///
     /// ```rust
@@ -897,6 +1454,11 @@ where I: SpIndex,
         }
     }
 
+    /// Create a dense matrix holding the same values as this matrix,
+    /// with zeros at the non-stored locations.
+    ///
+    /// The resulting array is in row-major order, whatever the storage of
+    /// this matrix.
     pub fn to_dense(&self) -> Array<N, Ix2>
     where N: Clone + Zero
     {
@@ -905,6 +1467,27 @@ where I: SpIndex,
         res
     }
 
+    /// Write the values of this matrix into the row-major buffer `out`,
+    /// with zeros at the non-stored locations.
+    ///
+    /// The buffer is entirely overwritten, which makes it possible to reuse
+    /// it for densifying several matrices without allocating.
+    ///
+    /// # Panics
+    ///
+    /// - if `out.len() != self.rows() * self.cols()`
+    pub fn densify_into(&self, out: &mut [N])
+    where N: Clone + Zero
+    {
+        assert_eq!(out.len(), self.nrows * self.ncols, "Dimension mismatch");
+        for val in out.iter_mut() {
+            *val = N::zero();
+        }
+        for (val, (row, col)) in self.iter() {
+            out[row.index() * self.ncols + col.index()] = val.clone();
+        }
+    }
+
     /// Return an outer iterator for the matrix
     ///
     /// This can be used for iterating over the rows (resp. cols) of
@@ -969,6 +1552,55 @@ where I: SpIndex,
         })
     }
 
+    /// Get a cursor over the i-th outer dimension (eg i-th row for a CSR
+    /// matrix), enabling fast lookups of its elements by increasing inner
+    /// index.
+    ///
+    /// # Panics
+    ///
+    /// - if `i >= self.outer_dims()`
+    pub fn outer_cursor(&self, i: usize) -> OuterCursor<N, I> {
+        let vec = self.outer_view(i).expect("Out of bounds outer index");
+        OuterCursor {
+            indices: vec.indices,
+            data: vec.data,
+            pos: 0,
+        }
+    }
+
+    /// Create an owned matrix holding the outer dimensions in `range`
+    /// (eg the rows in `range` for a CSR matrix). The inner dimension is
+    /// unchanged.
+    ///
+    /// # Panics
+    ///
+    /// - if `range.start > range.end` or `range.end > self.outer_dims()`
+    pub fn slice_outer(&self, range: Range<usize>) -> CsMatI<N, I>
+    where N: Clone
+    {
+        assert!(range.start <= range.end && range.end <= self.outer_dims(),
+                "Out of bounds outer range");
+        let offset = self.indptr[range.start];
+        let indptr: Vec<I> = self.indptr[range.start..range.end + 1]
+                                 .iter()
+                                 .map(|&x| x - offset)
+                                 .collect();
+        let nnz_range = offset.index()..self.indptr[range.end].index();
+        let outer = range.end - range.start;
+        let (nrows, ncols) = match self.storage {
+            CSR => (outer, self.ncols),
+            CSC => (self.nrows, outer),
+        };
+        CsMatI {
+            storage: self.storage,
+            nrows: nrows,
+            ncols: ncols,
+            indptr: indptr,
+            indices: self.indices[nnz_range.clone()].to_vec(),
+            data: self.data[nnz_range].to_vec(),
+        }
+    }
+
     /// Iteration on outer blocks of size block_size
     pub fn outer_block_iter(&self, block_size: usize
                            ) -> ChunkOuterBlocks<N, I> {
@@ -987,13 +1619,53 @@ where I: SpIndex,
         }
     }
 
-    pub fn map<F>(&self, f: F) -> CsMatI<N, I>
-    where F: FnMut(&N) -> N,
-          N: Clone
+    /// Apply a function to every non-zero element, yielding a new matrix
+    /// with the same sparsity structure. The function can change the type
+    /// of the elements.
+    ///
+    /// Note that the structure is kept even if the function maps some
+    /// elements to zero.
+    pub fn map<N2, F>(&self, f: F) -> CsMatI<N2, I>
+    where F: FnMut(&N) -> N2
     {
-        let mut res = self.to_owned();
-        res.map_inplace(f);
-        res
+        CsMatI {
+            storage: self.storage,
+            nrows: self.nrows,
+            ncols: self.ncols,
+            indptr: self.indptr.to_vec(),
+            indices: self.indices.to_vec(),
+            data: self.data.iter().map(f).collect(),
+        }
+    }
+
+    /// Compute the element-wise absolute value of this matrix, with the
+    /// same sparsity structure.
+    pub fn abs(&self) -> CsMatI<N, I>
+    where N: Signed
+    {
+        self.map(|x| x.abs())
+    }
+
+    /// Raise each non-zero element of this matrix to the integer power
+    /// `n`, keeping the same sparsity structure.
+    ///
+    /// This is an element-wise operation, not the matrix power: for
+    /// instance `powi(2)` squares each element, and differs from the
+    /// matrix product of this matrix with itself.
+    pub fn powi(&self, n: i32) -> CsMatI<N, I>
+    where N: Float
+    {
+        self.map(|x| x.powi(n))
+    }
+
+    /// Compute the element-wise complex conjugate of this matrix, with
+    /// the same sparsity structure.
+    ///
+    /// The conjugate transpose can then be obtained with `transpose_into`.
+    pub fn conj(&self) -> CsMatI<N, I>
+    where N: Conjugate
+    {
+        self.map(|x| x.conj())
     }
 
     /// Access an element given its outer_ind and inner_ind.
@@ -1016,6 +1688,11 @@ where I: SpIndex,
     /// in the corresponding outer slice.
     /// Once it is available, the NnzIndex enables retrieving the data with
     /// O(1) complexity.
+    ///
+    /// This can also be used to test whether a location is part of the
+    /// sparsity structure of the matrix, regardless of its value:
+    /// `mat.nnz_index(row, col).is_some()` is true even if an explicit zero
+    /// is stored at this location.
     pub fn nnz_index(&self, row: usize, col: usize) -> Option<NnzIndex> {
         match self.storage() {
             CSR => self.nnz_index_outer_inner(row, col),
@@ -1044,28 +1721,38 @@ where I: SpIndex,
     /// Check the structure of CsMat components
     /// This will ensure that:
     /// * indptr is of length outer_dim() + 1
+    /// * indptr starts at 0
     /// * indices and data have the same length, nnz == indptr[outer_dims()]
     /// * indptr is sorted
     /// * indptr values do not exceed usize::MAX / 2, as that would mean
     ///   indices and indptr would take more space than the addressable memory
     /// * indices is sorted for each outer slice
     /// * indices are lower than inner_dims()
+    ///
+    /// # Errors
+    ///
+    /// Returns the `SprsError` variant describing the first violated
+    /// property.
     pub fn check_compressed_structure(&self) -> Result<(), SprsError> {
         let outer = self.outer_dims();
 
         if self.indptr.len() != outer + 1 {
-            panic!("Indptr length does not match dimension");
+            return Err(SprsError::BadIndptrLength);
+        }
+        if self.indptr[0].index() != 0 {
+            // the indices before indptr[0] would never be checked
+            return Err(SprsError::OutOfBoundsIndptr);
         }
         if self.indices.len() != self.data.len() {
-            panic!("Indices and data lengths do not match");
+            return Err(SprsError::DataIndicesMismatch);
         }
         let nnz = self.indices.len();
         if nnz != self.nnz() {
-            panic!("Indices length and inpdtr's nnz do not match");
+            return Err(SprsError::BadNnzCount);
         }
         if let Some(&max_indptr) = self.indptr.iter().max() {
             if max_indptr.index() > nnz {
-                panic!("An indptr value is out of bounds");
+                return Err(SprsError::OutOfBoundsIndptr);
             }
             if max_indptr.index() > usize::max_value() / 2 {
                 // We do not allow indptr values to be larger than half
@@ -1073,7 +1760,7 @@ where I: SpIndex,
                 // all available memory
                 // This means we could have an isize, but in practice it's
                 // easier to work with usize for indexing.
-                panic!("An indptr value is larger than allowed");
+                return Err(SprsError::OutOfBoundsIndptr);
             }
         }
         else {
@@ -1096,6 +1783,11 @@ where I: SpIndex,
 
     /// Get an iterator that yields the non-zero locations and values stored in
     /// this matrix, in the fastest iteration order.
+    ///
+    /// The iteration order follows the storage: for a CSR matrix the
+    /// elements are yielded in row-major order, and for a CSC matrix in
+    /// column-major order. The yielded locations are always `(row, col)`,
+    /// whatever the storage.
     pub fn iter(&self) -> CsIter<N, I> {
         CsIter {
             storage: self.storage,
@@ -1107,1192 +1799,3995 @@ where I: SpIndex,
         }
     }
 
-}
-
-/// # Methods to convert between storage orders
-impl<N, I, IptrStorage, IndStorage, DataStorage>
-CsMatBase<N, I, IptrStorage, IndStorage, DataStorage>
-where N: Default,
-      I: SpIndex,
-      IptrStorage: Deref<Target=[I]>,
-      IndStorage: Deref<Target=[I]>,
-      DataStorage: Deref<Target=[N]> {
+    /// Fold over the non-zero elements of this matrix, in the iteration
+    /// order of `iter()`. The folding function receives the accumulator,
+    /// and the row, column and value of each element.
+    pub fn fold_entries<B, F>(&self, init: B, mut f: F) -> B
+    where F: FnMut(B, usize, usize, &N) -> B
+    {
+        let mut acc = init;
+        for (outer, vec) in self.outer_iterator().enumerate() {
+            for (inner, val) in vec.iter() {
+                acc = match self.storage {
+                    CSR => f(acc, outer, inner, val),
+                    CSC => f(acc, inner, outer, val),
+                };
+            }
+        }
+        acc
+    }
 
-    /// Create a matrix mathematically equal to this one, but with the
-    /// opposed storage (a CSC matrix will be converted to CSR, and vice versa)
-    pub fn to_other_storage(&self) -> CsMatI<N, I>
+    /// Convert this matrix to the triplet (COO) representation, returning
+    /// the row indices, column indices and values of its non-zeros, in the
+    /// iteration order of `iter()`.
+    ///
+    /// This is the inverse of `from_triplets`.
+    pub fn to_triplets(&self) -> (Vec<I>, Vec<I>, Vec<N>)
     where N: Clone
     {
-        let mut indptr = vec![I::zero(); self.inner_dims() + 1];
-        let mut indices = vec![I::zero(); self.nnz()];
-        let mut data = vec![N::default(); self.nnz()];
-        raw::convert_mat_storage(self.view(),
-                                 &mut indptr, &mut indices, &mut data);
-        CsMatI {
-            storage: self.storage().other_storage(),
-            nrows: self.nrows,
-            ncols: self.ncols,
-            indptr: indptr,
-            indices: indices,
-            data: data
+        let mut row_inds = Vec::with_capacity(self.nnz());
+        let mut col_inds = Vec::with_capacity(self.nnz());
+        let mut data = Vec::with_capacity(self.nnz());
+        for (val, (row, col)) in self.iter() {
+            row_inds.push(row);
+            col_inds.push(col);
+            data.push(val.clone());
         }
+        (row_inds, col_inds, data)
     }
 
-    /// Create a new CSC matrix equivalent to this one.
-    /// A new matrix will be created even if this matrix was already CSC.
-    pub fn to_csc(&self) -> CsMatI<N, I>
-    where N: Clone
-    {
-        match self.storage {
-            CSR => self.to_other_storage(),
-            CSC => self.to_owned()
+    /// Get an iterator that yields the non-zero values stored in this
+    /// matrix along with their `(outer, inner)` location, in storage order.
+    ///
+    /// For a CSR matrix this is the same as `iter()`, while for a CSC
+    /// matrix the yielded locations are `(col, row)`.
+    pub fn outer_inner_iter(&self) -> CsIter<N, I> {
+        CsIter {
+            storage: CSR,
+            cur_outer: I::from_usize(0),
+            indptr: &self.indptr[..],
+            inner_iter: self.indices.iter()
+                                    .zip(self.data.iter())
+                                    .enumerate(),
         }
     }
 
-    /// Create a new CSR matrix equivalent to this one.
-    /// A new matrix will be created even if this matrix was already CSR.
-    pub fn to_csr(&self) -> CsMatI<N, I>
-    where N: Clone
+    /// Compute the product of this matrix with a dense vector, returning
+    /// the result as a new dense vector.
+    ///
+    /// # Panics
+    ///
+    /// - if `x.len() != self.cols()`
+    pub fn mul_dense_vec(&self, x: &[N]) -> Vec<N>
+    where N: Num + Copy
     {
+        let mut res = vec![N::zero(); self.rows()];
         match self.storage {
-            CSR => self.to_owned(),
-            CSC => self.to_other_storage()
+            CSR => prod::mul_acc_mat_vec_csr(self.view(), x, &mut res),
+            CSC => prod::mul_acc_mat_vec_csc(self.view(), x, &mut res),
         }
+        res
     }
 
-}
-
-/// # Methods for sparse matrices holding mutable access to their values.
-impl<N, I, IptrStorage, IndStorage, DataStorage>
-CsMatBase<N, I, IptrStorage, IndStorage, DataStorage>
-where
-I: SpIndex,
-IptrStorage: Deref<Target=[I]>,
-IndStorage: Deref<Target=[I]>,
-DataStorage: DerefMut<Target=[N]> {
-
-    /// Mutable access to the non zero values
+    /// Compute the product of this matrix with a dense vector, distributing
+    /// the computation of the result's elements over several threads.
     ///
-    /// This enables changing the values without changing the matrix's
-    /// structure. To also change the matrix's structure,
-    /// see [modify](fn.modify.html)
-    pub fn data_mut(&mut self) -> &mut [N] {
-        &mut self.data[..]
+    /// Each element of the result is computed sequentially by a single
+    /// thread, so the result is deterministic and equal to the result
+    /// of `mul_dense_vec`. Only CSR matrices are computed in parallel, CSC
+    /// matrices fall back to `mul_dense_vec`.
+    ///
+    /// # Panics
+    ///
+    /// - if `x.len() != self.cols()`
+    #[cfg(feature = "rayon")]
+    pub fn mul_dense_vec_par(&self, x: &[N]) -> Vec<N>
+    where N: Num + Copy + Send + Sync,
+          I: Sync
+    {
+        use rayon::prelude::*;
+        if self.storage == CSC {
+            return self.mul_dense_vec(x);
+        }
+        assert_eq!(x.len(), self.cols(), "Dimension mismatch");
+        let indptr = &self.indptr[..];
+        let indices = &self.indices[..];
+        let data = &self.data[..];
+        (0..self.rows()).into_par_iter().map(|row| {
+            let start = indptr[row].index();
+            let stop = indptr[row + 1].index();
+            indices[start..stop].iter()
+                                .zip(data[start..stop].iter())
+                                .fold(N::zero(), |acc, (&col, &val)| {
+                                    acc + x[col.index()] * val
+                                })
+        }).collect()
     }
 
-    /// Sparse matrix self-multiplication by a scalar
-    pub fn scale(&mut self, val: N) where N: Num + Copy {
-        for data in self.data_mut() {
-            *data = *data * val;
+    /// Perform one step of the Jacobi iteration for the system `A x = b`,
+    /// ie compute for each `i`
+    /// `x_i' = (b_i - sum_{j != i} a_ij x_j) / a_ii`, and return `x'`.
+    ///
+    /// This step can be iterated until convergence, which is guaranteed
+    /// for strictly diagonally dominant matrices.
+    ///
+    /// # Errors
+    ///
+    /// `SingularMatrix` if a diagonal element is missing or zero.
+    ///
+    /// # Panics
+    ///
+    /// - if the matrix is not square
+    /// - if `x.len() != self.cols()` or `b.len() != self.rows()`
+    pub fn jacobi_step(&self, x: &[N], b: &[N]) -> Result<Vec<N>, SprsError>
+    where N: Num + Copy
+    {
+        assert_eq!(self.rows(), self.cols(), "Non square matrix");
+        assert_eq!(b.len(), self.rows(), "Dimension mismatch");
+        let diag = self.diag();
+        if diag.nnz() != self.rows()
+            || diag.data().iter().any(|&d| d == N::zero()) {
+            return Err(SprsError::SingularMatrix);
         }
+        let ax = self.mul_dense_vec(x);
+        Ok(diag.data().iter().enumerate().map(|(i, &d)| {
+            (b[i] - ax[i] + d * x[i]) / d
+        }).collect())
     }
 
-    /// Get a mutable view into the i-th outer dimension
-    /// (eg i-th row for a CSR matrix)
-    pub fn outer_view_mut(&mut self, i: usize) -> Option<CsVecViewMutI<N, I>> {
-        if i >= self.outer_dims() {
-            return None;
-        }
-        let start = self.indptr[i].index();
-        let stop = self.indptr[i+1].index();
-        // CsMat invariants imply CsVec invariants
-        Some(CsVecBase {
-            dim: self.inner_dims(),
-            indices: &self.indices[start..stop],
-            data: &mut self.data[start..stop],
+    /// Compute the product of a symmetric matrix with a dense vector, when
+    /// this matrix only stores one triangle (lower or upper) of the
+    /// symmetric matrix.
+    ///
+    /// Each stored off-diagonal element contributes to the result for both
+    /// its location and the symmetric location. Storing elements of both
+    /// triangles will thus give incorrect results.
+    ///
+    /// # Panics
+    ///
+    /// - if the matrix is not square
+    /// - if `x.len() != self.cols()`
+    pub fn mul_dense_vec_sym(&self, x: &[N]) -> Vec<N>
+    where N: Num + Copy
+    {
+        assert_eq!(self.rows(), self.cols(), "Non square matrix");
+        assert_eq!(x.len(), self.cols(), "Dimension mismatch");
+        let res = vec![N::zero(); self.rows()];
+        self.fold_entries(res, |mut res, row, col, &val| {
+            res[row] = res[row] + val * x[col];
+            if row != col {
+                res[col] = res[col] + val * x[row];
+            }
+            res
         })
     }
 
-    /// Get a mutable reference to the element located at row i and column j.
-    /// Will return None if there is no non-zero element at this location.
+    /// Permute the rows of this matrix, ie compute `P * A`. The i-th row
+    /// of the resulting matrix is the `perm.at(i)`-th row of this matrix.
     ///
-    /// This access is logarithmic in the number of non-zeros
-    /// in the corresponding outer slice. It is therefore advisable not to rely
-    /// on this for algorithms, and prefer outer_iterator_mut() which accesses
-    /// elements in storage order.
-    /// TODO: outer_iterator_mut is not yet implemented
-    pub fn get_mut(&mut self, i: usize, j: usize) -> Option<&mut N> {
+    /// # Panics
+    ///
+    /// - if `perm.dim() != self.rows()`
+    pub fn permute_rows(&self, perm: PermViewI<I>) -> CsMatI<N, I>
+    where N: Clone
+    {
+        assert_eq!(perm.dim(), self.rows());
         match self.storage {
-            CSR => self.get_outer_inner_mut(i, j),
-            CSC => self.get_outer_inner_mut(j, i)
+            CSR => self.permute_outer(perm),
+            CSC => self.permute_inner(perm),
         }
     }
 
-    /// Get a mutable reference to an element given its outer_ind and inner_ind.
-    /// Will return None if there is no non-zero element at this location.
+    /// Permute the columns of this matrix, ie compute `A * P^T`. The j-th
+    /// column of the resulting matrix is the `perm.at(j)`-th column of
+    /// this matrix.
     ///
-    /// This access is logarithmic in the number of non-zeros
-    /// in the corresponding outer slice. It is therefore advisable not to rely
-    /// on this for algorithms, and prefer outer_iterator_mut() which accesses
-    /// elements in storage order.
-    pub fn get_outer_inner_mut(&mut self,
-                              outer_ind: usize,
-                              inner_ind: usize
-                             ) -> Option<&mut N> {
-        if let Some(NnzIndex(index)) = self.nnz_index_outer_inner(outer_ind,
-                                                                  inner_ind) {
-            Some(&mut self.data[index])
-        }
-        else {
-            None
+    /// # Panics
+    ///
+    /// - if `perm.dim() != self.cols()`
+    pub fn permute_cols(&self, perm: PermViewI<I>) -> CsMatI<N, I>
+    where N: Clone
+    {
+        assert_eq!(perm.dim(), self.cols());
+        match self.storage {
+            CSR => self.permute_inner(perm),
+            CSC => self.permute_outer(perm),
         }
     }
 
-    /// Set the value of the non-zero element located at (row, col)
+    /// Apply a symmetric permutation to this matrix, ie compute
+    /// `P * A * P^T`. The element `(i, j)` of the resulting matrix is the
+    /// element `(perm.at(i), perm.at(j))` of this matrix.
+    ///
+    /// This is the permutation produced by fill-reducing orderings.
     ///
     /// # Panics
     ///
-    /// - on out-of-bounds access
-    /// - if no non-zero element exists at the given location
-    pub fn set(&mut self, row: usize, col: usize, val: N) {
-        let outer = outer_dimension(self.storage(), row, col);
-        let inner = inner_dimension(self.storage(), row, col);
-        let vec::NnzIndex(index) = self.outer_view(outer).and_then(|vec| {
-            vec.nnz_index(inner)
-        }).unwrap();
-        self.data[index] = val;
+    /// - if the matrix is not square
+    /// - if `perm.dim() != self.rows()`
+    pub fn papt(&self, perm: PermViewI<I>) -> CsMatI<N, I>
+    where N: Clone
+    {
+        assert_eq!(self.rows(), self.cols(), "Non square matrix");
+        assert_eq!(perm.dim(), self.rows());
+        self.permute_outer(perm.reborrow()).permute_inner(perm)
     }
 
-    /// Apply a function to every non-zero element
-    pub fn map_inplace<F>(&mut self, mut f: F)
-    where F: FnMut(&N) -> N
+    fn permute_outer(&self, perm: PermViewI<I>) -> CsMatI<N, I>
+    where N: Clone
     {
-        for val in &mut self.data[..] {
-            *val = f(val);
+        let mut res = CsMatI::empty(self.storage, self.inner_dims());
+        res.reserve_outer_dim_exact(self.outer_dims());
+        res.reserve_nnz_exact(self.nnz());
+        for outer_ind in 0..self.outer_dims() {
+            let vec = self.outer_view(perm.at(outer_ind)).unwrap();
+            res = res.append_outer_csvec(vec);
         }
+        res
     }
 
-    /// Return a mutable outer iterator for the matrix
-    ///
-    /// This iterator yields mutable sparse vector views for each outer
-    /// dimension. Only the non-zero values can be modified, the
-    /// structure is kept immutable.
-    pub fn outer_iterator_mut<'a>(&'a mut self) -> OuterIteratorMut<'a, N, I> {
-        let inner_len = match self.storage {
-            CSR => self.ncols,
-            CSC => self.nrows
-        };
-        OuterIteratorMut {
-            inner_len: inner_len,
-            indptr_iter: self.indptr.windows(2),
-            indices: &self.indices[..],
-            data: &mut self.data[..],
+    fn permute_inner(&self, perm: PermViewI<I>) -> CsMatI<N, I>
+    where N: Clone
+    {
+        let mut indptr = Vec::with_capacity(self.outer_dims() + 1);
+        let mut indices = Vec::with_capacity(self.nnz());
+        let mut data = Vec::with_capacity(self.nnz());
+        let mut buf = Vec::new();
+        indptr.push(I::zero());
+        for window in self.indptr.windows(2) {
+            let range = window[0].index()..window[1].index();
+            buf.clear();
+            buf.extend(range.map(|k| {
+                (perm.at_inv(self.indices[k].index()), k)
+            }));
+            buf.sort_by_key(|&(ind, _)| ind);
+            for &(ind, k) in &buf {
+                indices.push(I::from_usize(ind));
+                data.push(self.data[k].clone());
+            }
+            indptr.push(I::from_usize(indices.len()));
+        }
+        CsMatI {
+            storage: self.storage,
+            nrows: self.nrows,
+            ncols: self.ncols,
+            indptr: indptr,
+            indices: indices,
+            data: data,
         }
     }
 
-}
+    /// Get the indices of the outer dimensions that have no stored
+    /// element. For a CSR matrix, these are the empty rows, which can be
+    /// the sign of a singular system.
+    pub fn empty_outer_indices(&self) -> Vec<usize> {
+        self.indptr.windows(2)
+                   .enumerate()
+                   .filter(|&(_, w)| w[0] == w[1])
+                   .map(|(i, _)| i)
+                   .collect()
+    }
 
-impl<N, I, IptrStorage, IndStorage, DataStorage>
-CsMatBase<N, I, IptrStorage, IndStorage, DataStorage>
-where
-I: SpIndex,
-IptrStorage: DerefMut<Target=[I]>,
-IndStorage: DerefMut<Target=[I]>,
-DataStorage: DerefMut<Target=[N]> {
-    /// Modify the matrix's structure without changing its nonzero count.
-    ///
-    /// The coherence of the structure will be checked afterwards.
+    /// Compute the sum of the elements of each outer dimension (eg the
+    /// sum of each row for a CSR matrix).
+    pub fn outer_sums(&self) -> Vec<N>
+    where N: Num + Copy
+    {
+        self.outer_iterator()
+            .map(|vec| vec.data().iter().fold(N::zero(), |acc, &x| acc + x))
+            .collect()
+    }
+
+    /// Compute the sum of the elements of each inner dimension (eg the
+    /// sum of each column for a CSR matrix).
+    pub fn inner_sums(&self) -> Vec<N>
+    where N: Num + Copy
+    {
+        let mut res = vec![N::zero(); self.inner_dims()];
+        for (&ind, &val) in self.indices.iter().zip(self.data.iter()) {
+            res[ind.index()] = res[ind.index()] + val;
+        }
+        res
+    }
+
+    /// Compute the infinity norm of this matrix, ie its maximum absolute
+    /// row sum.
     ///
-    /// # Panics
+    /// This is cheaper for a CSR matrix, where each row is summed
+    /// independently, than for a CSC matrix, where the row sums need to be
+    /// accumulated in a temporary vector.
+    pub fn norm_inf(&self) -> N
+    where N: Signed + PartialOrd + Copy
+    {
+        let abs = self.abs();
+        let row_sums = match self.storage {
+            CSR => abs.outer_sums(),
+            CSC => abs.inner_sums(),
+        };
+        max_or_zero(&row_sums)
+    }
+
+    /// Compute the one norm of this matrix, ie its maximum absolute
+    /// column sum.
     ///
-    /// If the resulting matrix breaks the CsMat invariants (sorted indices,
-    /// no out of bounds indices).
+    /// This is cheaper for a CSC matrix than for a CSR matrix.
+    pub fn norm_one(&self) -> N
+    where N: Signed + PartialOrd + Copy
+    {
+        let abs = self.abs();
+        let col_sums = match self.storage {
+            CSR => abs.inner_sums(),
+            CSC => abs.outer_sums(),
+        };
+        max_or_zero(&col_sums)
+    }
+
+    /// Test whether this matrix is diagonally dominant, ie whether the
+    /// absolute value of the diagonal element of each row is greater than or
+    /// equal to the sum of the absolute values of the other elements of
+    /// the row. If `strict` is true, the diagonal element has to be strictly
+    /// greater.
     ///
-    /// # Example
+    /// A missing diagonal element counts as zero, so its row fails the
+    /// test unless it is empty and `strict` is false. Non square matrices
+    /// are not diagonally dominant.
     ///
-    /// ```rust
-    /// use sprs::CsMat;
-    /// // |   1   |
-    /// // | 1     |
-    /// // |   1 1 |
-    /// let mut mat = CsMat::new_csc((3, 3),
-    ///                                   vec![0, 1, 3, 4],
-    ///                                   vec![1, 0, 2, 2],
-    ///                                   vec![1.; 4]);
+    /// Strict diagonal dominance guarantees the convergence of the Jacobi
+    /// and Gauss-Seidel iterations.
+    pub fn is_diagonally_dominant(&self, strict: bool) -> bool
+    where N: Signed + PartialOrd + Copy
+    {
+        if self.nrows != self.ncols {
+            return false;
+        }
+        let abs = self.abs();
+        let row_sums = match self.storage {
+            CSR => abs.outer_sums(),
+            CSC => abs.inner_sums(),
+        };
+        let mut diag = vec![N::zero(); self.nrows];
+        for (i, &val) in abs.diag().iter() {
+            diag[i] = val;
+        }
+        row_sums.iter().zip(diag.iter()).all(|(&sum, &d)| {
+            let off_diag = sum - d;
+            if strict { d > off_diag } else { d >= off_diag }
+        })
+    }
+
+    /// Test whether this matrix and `other` have the same sparsity
+    /// structure, ie the same storage, shape, `indptr` and `indices`.
+    /// The stored values are not compared.
     ///
-    /// // | 1 2   |
-    /// // | 1     |
-    /// // |   1   |
-    /// mat.modify(|indptr, indices, data| {
-    ///     indptr[1] = 2;
-    ///     indptr[2] = 4;
-    ///     indices[0] = 0;
-    ///     indices[1] = 1;
-    ///     indices[2] = 0;
-    ///     data[2] = 2.;
-    /// });
-    /// ```
-    pub fn modify<F>(&mut self, mut f: F)
-    where F: FnMut(&mut [I], &mut [I], &mut [N]) {
-        f(&mut self.indptr[..], &mut self.indices[..], &mut self.data[..]);
-        self.check_compressed_structure().unwrap();
+    /// This enables eg reusing a symbolic factorization between matrices
+    /// sharing the same structure.
+    pub fn same_sparsity<N2, IpS2, IS2, DS2>(
+        &self,
+        other: &CsMatBase<N2, I, IpS2, IS2, DS2>
+    ) -> bool
+    where IpS2: Deref<Target=[I]>,
+          IS2: Deref<Target=[I]>,
+          DS2: Deref<Target=[N2]>
+    {
+        self.storage == other.storage()
+            && self.shape() == other.shape()
+            && self.indptr[..] == other.indptr()[..]
+            && self.indices[..] == other.indices()[..]
     }
-}
 
-/// Raw functions acting directly on the compressed structure.
-pub mod raw {
-    use sparse::prelude::*;
-    use indexing::SpIndex;
-    use ::Shape;
-    use std::mem::swap;
+    /// Compute the Frobenius norm of this matrix, ie the square root of
+    /// the sum of the squares of its elements.
+    pub fn frobenius_norm(&self) -> N
+    where N: Float
+    {
+        self.data.iter().fold(N::zero(), |acc, &x| acc + x * x).sqrt()
+    }
 
-    /// Copy-convert a compressed matrix into the oppposite storage.
-    ///
-    /// The input compressed matrix does not need to have its indices sorted,
-    /// but the output compressed matrix will have its indices sorted.
+    /// Compute the element-wise (Hadamard) product of this matrix with
+    /// `other`.
     ///
-    /// Can be used to implement CSC <-> CSR conversions, or to implement
-    /// same-storage (copy) transposition.
+    /// The non-zero pattern of the result is the intersection of the
+    /// patterns of both operands. Returns `None` if the matrices do not
+    /// have the same shape and storage.
+    pub fn hadamard<IpS2, IS2, DS2>(&self,
+                                    other: &CsMatBase<N, I, IpS2, IS2, DS2>
+                                   ) -> Option<CsMatI<N, I>>
+    where N: Num + Copy,
+          IpS2: Deref<Target=[I]>,
+          IS2: Deref<Target=[I]>,
+          DS2: Deref<Target=[N]>
+    {
+        if self.shape() != other.shape() || self.storage != other.storage() {
+            return None;
+        }
+        Some(binop::mul_mat_same_storage(self, other))
+    }
+
+    /// Find the maximum stored entry of this matrix, returning its
+    /// `(row, col)` location and its value, or `None` if the matrix has
+    /// no stored entries.
     ///
-    /// # Panics
+    /// Only the stored entries are considered, implicit zeros are ignored.
+    /// For instance, a matrix with only negative stored entries will
+    /// report a negative maximum even if it has missing elements.
     ///
-    /// Panics if indptr contains non-zero values
+    /// If several entries are maximal, the first one in storage order is
+    /// returned.
+    pub fn max_entry(&self) -> Option<(usize, usize, N)>
+    where N: PartialOrd + Copy
+    {
+        self.extremal_entry(|new, cur| new > cur)
+    }
+
+    /// Find the minimum stored entry of this matrix, returning its
+    /// `(row, col)` location and its value, or `None` if the matrix has
+    /// no stored entries.
     ///
-    /// Panics if the output slices don't match the input matrices'
-    /// corresponding slices.
-    pub fn convert_storage<N, I>(in_storage: super::CompressedStorage,
-                                 shape: Shape,
-                                 in_indtpr: &[I],
-                                 in_indices: &[I],
-                                 in_data: &[N],
-                                 indptr: &mut [I],
-                                 indices: &mut[I],
-                                 data: &mut [N])
-    where N: Clone, I: SpIndex {
-        // we're building a csmat even though the indices are not sorted,
-        // but it's not a problem since we don't rely on this property.
-        // FIXME: this would be better with an explicit unsorted matrix type
-        let mat = CsMatBase {
-            storage: in_storage,
-            nrows: shape.0,
-            ncols: shape.1,
-            indptr: in_indtpr,
-            indices: in_indices,
-            data: in_data,
-        };
+    /// As for `max_entry`, only the stored entries are considered.
+    pub fn min_entry(&self) -> Option<(usize, usize, N)>
+    where N: PartialOrd + Copy
+    {
+        self.extremal_entry(|new, cur| new < cur)
+    }
 
-        convert_mat_storage(mat, indptr, indices, data);
+    fn extremal_entry<F>(&self, better: F) -> Option<(usize, usize, N)>
+    where N: Copy,
+          F: Fn(&N, &N) -> bool
+    {
+        self.fold_entries(None, |res, row, col, &val| {
+            let replace = match res {
+                None => true,
+                Some((_, _, cur)) => better(&val, &cur),
+            };
+            if replace { Some((row, col, val)) } else { res }
+        })
     }
 
-    /// Copy-convert a csmat into the oppposite storage.
+    /// Extract the main diagonal of this matrix as a sparse vector.
     ///
-    /// Can be used to implement CSC <-> CSR conversions, or to implement
-    /// same-storage (copy) transposition.
+    /// The resulting vector has dimension `min(self.rows(), self.cols())`,
+    /// and only holds the diagonal elements that are stored in this matrix.
+    pub fn diag(&self) -> CsVecI<N, I>
+    where N: Clone
+    {
+        let dim = cmp::min(self.nrows, self.ncols);
+        let mut res = CsVecI::empty(dim);
+        for (i, vec) in self.outer_iterator().take(dim).enumerate() {
+            if let Some(val) = vec.get(i) {
+                res.append(i, val.clone());
+            }
+        }
+        res
+    }
+
+    /// Test whether this matrix is symmetric, ie whether it is square and
+    /// each stored element `(i, j, v)` has a matching `(j, i, v)` element.
     ///
-    /// # Panics
+    /// This is a method form of
+    /// [`is_symmetric`](fn.is_symmetric.html).
+    pub fn is_symmetric(&self) -> bool
+    where N: Copy + PartialEq
+    {
+        symmetric::is_symmetric(self)
+    }
+
+    /// Create a copy of this matrix embedded in a larger shape, padded
+    /// with zero rows and columns. The elements keep their locations.
     ///
-    /// Panics if indptr contains non-zero values
+    /// Returns `None` if the new shape is smaller than the current shape
+    /// in any dimension.
+    pub fn grow(&self,
+                new_rows: usize,
+                new_cols: usize
+               ) -> Option<CsMatI<N, I>>
+    where N: Clone
+    {
+        if new_rows < self.nrows || new_cols < self.ncols {
+            return None;
+        }
+        let new_outer_dims = match self.storage {
+            CSR => new_rows,
+            CSC => new_cols,
+        };
+        let mut indptr = self.indptr.to_vec();
+        let nnz = I::from_usize(self.nnz());
+        indptr.resize(new_outer_dims + 1, nnz);
+        Some(CsMatI {
+            storage: self.storage,
+            nrows: new_rows,
+            ncols: new_cols,
+            indptr: indptr,
+            indices: self.indices.to_vec(),
+            data: self.data.to_vec(),
+        })
+    }
+
+    /// Compute the lower and upper bandwidths of this matrix, ie the
+    /// maximum of `row - col` and of `col - row` over its non-zero
+    /// elements. A diagonal matrix has a `(0, 0)` bandwidth, and a
+    /// tridiagonal matrix has a `(1, 1)` bandwidth.
+    pub fn bandwidth(&self) -> (usize, usize) {
+        self.fold_entries((0, 0), |(lower, upper), row, col, _| {
+            if row > col {
+                (cmp::max(lower, row - col), upper)
+            } else {
+                (lower, cmp::max(upper, col - row))
+            }
+        })
+    }
+
+    /// Extract the lower triangular part of this matrix, ie the elements
+    /// `(row, col)` such that `col <= row + k`.
     ///
-    /// Panics if the output slices don't match the input matrices'
-    /// corresponding slices.
-    pub fn convert_mat_storage<N: Clone, I: SpIndex>(mat: CsMatViewI<N, I>,
-                                                     indptr: &mut [I],
-                                                     indices: &mut[I],
-                                                     data: &mut [N]) {
-        assert_eq!(indptr.len(), mat.inner_dims() + 1);
-        assert_eq!(indices.len(), mat.indices().len());
-        assert_eq!(data.len(), mat.data().len());
+    /// `k = 0` gives the lower triangle including the diagonal, `k = -1`
+    /// gives the strictly lower triangle.
+    pub fn tril(&self, k: isize) -> CsMatI<N, I>
+    where N: Clone
+    {
+        self.filter(|row, col, _| col as isize - row as isize <= k)
+    }
 
-        assert!(indptr.iter().all(|x| *x == I::zero()));
+    /// Extract the upper triangular part of this matrix, ie the elements
+    /// `(row, col)` such that `col >= row + k`.
+    ///
+    /// `k = 0` gives the upper triangle including the diagonal, `k = 1`
+    /// gives the strictly upper triangle.
+    pub fn triu(&self, k: isize) -> CsMatI<N, I>
+    where N: Clone
+    {
+        self.filter(|row, col, _| col as isize - row as isize >= k)
+    }
 
-        for vec in mat.outer_iterator() {
-            for (inner_dim, _) in vec.iter() {
-                indptr[inner_dim] += I::one();
+    /// Split this matrix into its strictly lower triangular part, its
+    /// main diagonal and its strictly upper triangular part, in a single
+    /// pass over the non-zero elements.
+    ///
+    /// This is equivalent to `(self.tril(-1), self.diag(), self.triu(1))`,
+    /// and is the decomposition used by stationary iterative methods
+    /// such as Jacobi or Gauss-Seidel.
+    pub fn split_ldu(&self) -> (CsMatI<N, I>, CsVecI<N, I>, CsMatI<N, I>)
+    where N: Clone
+    {
+        let outer_dims = self.outer_dims();
+        let mut diag = CsVecI::empty(cmp::min(self.nrows, self.ncols));
+        let mut parts = [(Vec::with_capacity(outer_dims + 1),
+                          Vec::new(),
+                          Vec::new()),
+                         (Vec::with_capacity(outer_dims + 1),
+                          Vec::new(),
+                          Vec::new())];
+        for &mut (ref mut indptr, _, _) in parts.iter_mut() {
+            indptr.push(I::zero());
+        }
+        for (outer, vec) in self.outer_iterator().enumerate() {
+            for (inner, val) in vec.iter() {
+                let (row, col) = match self.storage {
+                    CSR => (outer, inner),
+                    CSC => (inner, outer),
+                };
+                if row == col {
+                    diag.append(row, val.clone());
+                    continue;
+                }
+                let part = if row > col { 0 } else { 1 };
+                parts[part].1.push(I::from_usize(inner));
+                parts[part].2.push(val.clone());
+            }
+            for &mut (ref mut indptr, ref indices, _) in parts.iter_mut() {
+                indptr.push(I::from_usize(indices.len()));
             }
         }
+        let [(l_indptr, l_indices, l_data),
+             (u_indptr, u_indices, u_data)] = parts;
+        let lower = CsMatI {
+            storage: self.storage,
+            nrows: self.nrows,
+            ncols: self.ncols,
+            indptr: l_indptr,
+            indices: l_indices,
+            data: l_data,
+        };
+        let upper = CsMatI {
+            storage: self.storage,
+            nrows: self.nrows,
+            ncols: self.ncols,
+            indptr: u_indptr,
+            indices: u_indices,
+            data: u_data,
+        };
+        (lower, diag, upper)
+    }
 
-        let mut cumsum = I::zero();
-        for iptr in indptr.iter_mut() {
-            let tmp = *iptr;
-            *iptr = cumsum;
-            cumsum += tmp;
+    /// Create a matrix holding only the non-zero elements of this matrix
+    /// for which the predicate `pred` holds. The predicate receives the
+    /// row, column and value of each element.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sprs::CsMat;
+    /// let mat = CsMat::new((2, 2), vec![0, 2, 3], vec![0, 1, 1],
+    ///                      vec![1., 1e-9, -2.]);
+    /// let res = mat.filter(|_, _, &val: &f64| val.abs() > 1e-6);
+    /// assert_eq!(res.nnz(), 2);
+    /// ```
+    pub fn filter<F>(&self, pred: F) -> CsMatI<N, I>
+    where N: Clone,
+          F: Fn(usize, usize, &N) -> bool
+    {
+        let mut indptr = Vec::with_capacity(self.outer_dims() + 1);
+        let mut indices = Vec::new();
+        let mut data = Vec::new();
+        indptr.push(I::zero());
+        for (outer, vec) in self.outer_iterator().enumerate() {
+            for (inner, val) in vec.iter() {
+                let (row, col) = match self.storage {
+                    CSR => (outer, inner),
+                    CSC => (inner, outer),
+                };
+                if pred(row, col, val) {
+                    indices.push(I::from_usize(inner));
+                    data.push(val.clone());
+                }
+            }
+            indptr.push(I::from_usize(indices.len()));
         }
-        if let Some(last_iptr) = indptr.last() {
-            assert_eq!(last_iptr.index(), mat.nnz());
+        CsMatI {
+            storage: self.storage,
+            nrows: self.nrows,
+            ncols: self.ncols,
+            indptr: indptr,
+            indices: indices,
+            data: data,
         }
+    }
 
-        for (outer_dim, vec) in mat.outer_iterator().enumerate() {
-            for (inner_dim, val) in vec.iter() {
-                let dest = indptr[inner_dim].index();
-                data[dest] = val.clone();
-                indices[dest] = I::from_usize(outer_dim);
-                indptr[inner_dim] += I::one();
+    /// Extract the inner dimension `j` of this matrix (eg the `j`-th
+    /// column of a CSR matrix) as a sparse vector.
+    ///
+    /// Contrary to `outer_view`, this is not a cheap operation: as the
+    /// inner dimension is not stored contiguously, a binary search is
+    /// performed in each outer dimension, for a total complexity of
+    /// `O(outer_dims * log(nnz_per_outer))`. When accessing many inner
+    /// dimensions, converting the matrix to the other storage and using
+    /// `outer_view` will be more efficient.
+    ///
+    /// # Panics
+    ///
+    /// - if `j >= self.inner_dims()`
+    pub fn inner_view(&self, j: usize) -> CsVecI<N, I>
+    where N: Clone
+    {
+        assert!(j < self.inner_dims(), "Out of bounds inner index");
+        let mut res = CsVecI::empty(self.outer_dims());
+        for (i, vec) in self.outer_iterator().enumerate() {
+            if let Some(val) = vec.get(j) {
+                res.append(i, val.clone());
             }
         }
-
-        let mut last = I::zero();
-        for iptr in indptr.iter_mut() {
-            swap(iptr, &mut last);
-        }
+        res
     }
-}
 
-impl<'a, N:'a, I: 'a + SpIndex> CsMatBase<N, I, Vec<I>, &'a [I], &'a [N]> {
-    /// Create a borrowed row or column CsMat matrix from raw data,
-    /// without checking their validity
+    /// Compute the trace of this matrix, ie the sum of its diagonal
+    /// elements. Diagonal elements that are not stored count as zeros.
     ///
-    /// This is unsafe because algorithms are free to assume
-    /// that properties guaranteed by check_compressed_structure are enforced.
-    /// For instance, non out-of-bounds indices can be relied upon to
-    /// perform unchecked slice access.
-    pub unsafe fn new_vecview_raw(
-        storage: CompressedStorage, nrows : usize, ncols: usize,
-        indptr : *const I, indices : *const I, data : *const N
-        )
-    -> CsMatVecView_<'a, N, I> {
-        let indptr = slice::from_raw_parts(indptr, 2);
-        let nnz = indptr[1].index();
-        CsMatVecView_ {
-            storage: storage,
-            nrows : nrows,
-            ncols: ncols,
-            indptr : Array2 {data: [indptr[0], indptr[1]]},
-            indices : slice::from_raw_parts(indices, nnz),
-            data : slice::from_raw_parts(data, nnz),
-        }
+    /// # Panics
+    ///
+    /// - if the matrix is not square.
+    pub fn trace(&self) -> N
+    where N: Zero + Clone
+    {
+        assert_eq!(self.nrows, self.ncols, "Trace of a non square matrix");
+        self.diag().data().iter().fold(N::zero(), |acc, x| acc + x.clone())
     }
+
 }
 
-impl<'a, 'b, N, I, IpStorage, IStorage, DStorage, IpS2, IS2, DS2>
-Add<&'b CsMatBase<N, I, IpS2, IS2, DS2>>
-for &'a CsMatBase<N, I, IpStorage, IStorage, DStorage>
-where N: 'a + Copy + Num + Default,
-      I: 'a + SpIndex,
-      IpStorage: 'a + Deref<Target=[I]>,
-      IStorage: 'a + Deref<Target=[I]>,
-      DStorage: 'a + Deref<Target=[N]>,
-      IpS2: 'a + Deref<Target=[I]>,
-      IS2: 'a + Deref<Target=[I]>,
-      DS2: 'a + Deref<Target=[N]> {
-    type Output = CsMatI<N, I>;
+/// # Methods to convert between storage orders
+impl<N, I, IptrStorage, IndStorage, DataStorage>
+CsMatBase<N, I, IptrStorage, IndStorage, DataStorage>
+where N: Default,
+      I: SpIndex,
+      IptrStorage: Deref<Target=[I]>,
+      IndStorage: Deref<Target=[I]>,
+      DataStorage: Deref<Target=[N]> {
 
-    fn add(self, rhs: &'b CsMatBase<N, I, IpS2, IS2, DS2>) -> CsMatI<N, I> {
-        if self.storage() != rhs.view().storage() {
-            return binop::add_mat_same_storage(
-                self, &rhs.view().to_other_storage())
+    /// Create a matrix mathematically equal to this one, but with the
+    /// opposed storage (a CSC matrix will be converted to CSR, and vice versa)
+    pub fn to_other_storage(&self) -> CsMatI<N, I>
+    where N: Clone
+    {
+        let mut indptr = vec![I::zero(); self.inner_dims() + 1];
+        let mut indices = vec![I::zero(); self.nnz()];
+        let mut data = vec![N::default(); self.nnz()];
+        raw::convert_mat_storage(self.view(),
+                                 &mut indptr, &mut indices, &mut data);
+        CsMatI {
+            storage: self.storage().other_storage(),
+            nrows: self.nrows,
+            ncols: self.ncols,
+            indptr: indptr,
+            indices: indices,
+            data: data
         }
-        binop::add_mat_same_storage(self, rhs)
     }
-}
 
-impl<'a, 'b, N, I, IpStorage, IStorage, DStorage, Mat> Sub<&'b Mat>
-for &'a CsMatBase<N, I, IpStorage, IStorage, DStorage>
-where N: 'a + Copy + Num + Default,
-      I: 'a + SpIndex,
-      IpStorage: 'a + Deref<Target=[I]>,
-      IStorage: 'a + Deref<Target=[I]>,
-      DStorage: 'a + Deref<Target=[N]>,
-      Mat: SpMatView<N, I> {
-    type Output = CsMatI<N, I>;
+    /// Create a new CSC matrix equivalent to this one.
+    /// A new matrix will be created even if this matrix was already CSC.
+    pub fn to_csc(&self) -> CsMatI<N, I>
+    where N: Clone
+    {
+        match self.storage {
+            CSR => self.to_other_storage(),
+            CSC => self.to_owned()
+        }
+    }
 
-    fn sub(self, rhs: &'b Mat) -> CsMatI<N, I> {
-        if self.storage() != rhs.view().storage() {
-            return binop::sub_mat_same_storage(
-                self, &rhs.view().to_other_storage())
+    /// Create a new CSR matrix equivalent to this one.
+    /// A new matrix will be created even if this matrix was already CSR.
+    pub fn to_csr(&self) -> CsMatI<N, I>
+    where N: Clone
+    {
+        match self.storage {
+            CSR => self.to_owned(),
+            CSC => self.to_other_storage()
         }
-        binop::sub_mat_same_storage(self, rhs)
     }
-}
 
-macro_rules! sparse_scalar_mul {
-    ($scalar: ident) => (
-        impl<'a, I, IpStorage, IStorage, DStorage> Mul<$scalar>
-        for &'a CsMatBase<$scalar, I, IpStorage, IStorage, DStorage>
-        where I: 'a + SpIndex,
-              IpStorage: 'a + Deref<Target=[I]>,
-              IStorage: 'a + Deref<Target=[I]>,
-              DStorage: 'a + Deref<Target=[$scalar]> {
-            type Output = CsMatI<$scalar, I>;
+    /// Create the transpose of this matrix, keeping the same storage.
+    ///
+    /// Contrary to `transpose_view` and `transpose_into`, which only
+    /// change the storage flag, this rebuilds the compressed structure,
+    /// so that eg the transpose of a CSR matrix is also a CSR matrix.
+    pub fn transpose_same_storage(&self) -> CsMatI<N, I>
+    where N: Clone
+    {
+        self.transpose_view().to_other_storage()
+    }
 
-            fn mul(self, rhs: $scalar) -> CsMatI<$scalar, I> {
-                binop::scalar_mul_mat(self, rhs)
+    /// Compute the Kronecker product of this matrix with `other`.
+    ///
+    /// For a `m x n` matrix `A` and a `p x q` matrix `B`, the result is
+    /// the `mp x nq` matrix having the value `A[i, j] * B[k, l]` at the
+    /// location `(i * p + k, j * q + l)`. The result has the storage of
+    /// this matrix.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sprs::CsMat;
+    /// let eye: CsMat<f64> = CsMat::eye(2);
+    /// let a = CsMat::new((1, 2), vec![0, 2], vec![0, 1], vec![1., 2.]);
+    /// let expected = CsMat::new((2, 4),
+    ///                           vec![0, 2, 4],
+    ///                           vec![0, 1, 2, 3],
+    ///                           vec![1., 2., 1., 2.]);
+    /// assert_eq!(eye.kron(&a), expected);
+    /// ```
+    pub fn kron<IpS2, IS2, DS2>(&self,
+                                other: &CsMatBase<N, I, IpS2, IS2, DS2>
+                               ) -> CsMatI<N, I>
+    where N: Num + Copy,
+          IpS2: Deref<Target=[I]>,
+          IS2: Deref<Target=[I]>,
+          DS2: Deref<Target=[N]>
+    {
+        let other = match self.storage {
+            CSR => other.to_csr(),
+            CSC => other.to_csc(),
+        };
+        let nnz = self.nnz() * other.nnz();
+        let outer_dims = self.outer_dims() * other.outer_dims();
+        let other_inner_dims = other.inner_dims();
+        let mut indptr = Vec::with_capacity(outer_dims + 1);
+        let mut indices = Vec::with_capacity(nnz);
+        let mut data = Vec::with_capacity(nnz);
+        indptr.push(I::zero());
+        for lvec in self.outer_iterator() {
+            for rvec in other.outer_iterator() {
+                for (j, &a) in lvec.iter() {
+                    for (l, &b) in rvec.iter() {
+                        indices.push(I::from_usize(j * other_inner_dims + l));
+                        data.push(a * b);
+                    }
+                }
+                indptr.push(I::from_usize(indices.len()));
             }
         }
-    )
+        CsMatI {
+            storage: self.storage,
+            nrows: self.nrows * other.rows(),
+            ncols: self.ncols * other.cols(),
+            indptr: indptr,
+            indices: indices,
+            data: data,
+        }
+    }
+
+    /// Compute the Gram matrix `A^T * A` of this matrix `A`, as a CSR
+    /// matrix. For a `m x n` matrix, the result is a symmetric `n x n`
+    /// matrix, as needed by eg the normal equations of a least squares
+    /// problem.
+    ///
+    /// Only the lower triangle of the product is computed, the upper
+    /// triangle being obtained by symmetry.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sprs::CsMat;
+    /// let a = CsMat::new((3, 2), vec![0, 1, 3, 4], vec![0, 0, 1, 1],
+    ///                    vec![1., 2., 3., 4.]);
+    /// let gram = a.gram();
+    /// assert_eq!(gram.to_dense(), (&a.transpose_view() * &a).to_dense());
+    /// ```
+    pub fn gram(&self) -> CsMatI<N, I>
+    where N: Num + Copy
+    {
+        // iterating requires both the rows and the columns of A, only
+        // the storage this matrix does not have is converted
+        let other = self.to_other_storage();
+        let (rows, cols) = match self.storage {
+            CSR => (self.view(), other.view()),
+            CSC => (other.view(), self.view()),
+        };
+        let n = self.ncols;
+        let mut workspace = vec![N::zero(); n];
+        let mut touched = vec![false; n];
+        let mut pattern = Vec::new();
+        let mut lower_indptr = Vec::with_capacity(n + 1);
+        let mut lower_indices = Vec::new();
+        let mut lower_data = Vec::new();
+        // number of elements in each row of the strict upper triangle
+        let mut upper_counts = vec![0; n];
+        lower_indptr.push(0);
+        // row j of the lower triangle is the sum over the rows r having
+        // a non-zero in column j of A[r, j] * A[r, k], for k <= j
+        for (j, col) in cols.outer_iterator().enumerate() {
+            for (r, &a_rj) in col.iter() {
+                let row = rows.outer_view(r).unwrap();
+                for (k, &a_rk) in row.iter().take_while(|&(k, _)| k <= j) {
+                    if !touched[k] {
+                        touched[k] = true;
+                        pattern.push(k);
+                    }
+                    workspace[k] = workspace[k] + a_rj * a_rk;
+                }
+            }
+            pattern.sort();
+            for k in pattern.drain(..) {
+                if k < j {
+                    upper_counts[k] += 1;
+                }
+                lower_indices.push(k);
+                lower_data.push(workspace[k]);
+                workspace[k] = N::zero();
+                touched[k] = false;
+            }
+            lower_indptr.push(lower_indices.len());
+        }
+
+        // each row i of the result is made of row i of the lower triangle,
+        // followed by the mirrored elements of column i of the lower
+        // triangle, which are visited by increasing row
+        let mut indptr = Vec::with_capacity(n + 1);
+        indptr.push(0);
+        for (lower_range, &count) in lower_indptr.windows(2)
+                                                 .zip(upper_counts.iter()) {
+            let nnz = indptr.last().unwrap() + lower_range[1]
+                - lower_range[0] + count;
+            indptr.push(nnz);
+        }
+        let nnz = indptr[n];
+        let mut indices = vec![I::zero(); nnz];
+        let mut data = vec![N::zero(); nnz];
+        let mut next = Vec::with_capacity(n);
+        for (i, lower_range) in lower_indptr.windows(2).enumerate() {
+            let (start, stop) = (lower_range[0], lower_range[1]);
+            let dest = indptr[i];
+            for (pos, (&k, &val)) in lower_indices[start..stop]
+                                         .iter()
+                                         .zip(&lower_data[start..stop])
+                                         .enumerate() {
+                indices[dest + pos] = I::from_usize(k);
+                data[dest + pos] = val;
+            }
+            next.push(dest + stop - start);
+        }
+        for (j, lower_range) in lower_indptr.windows(2).enumerate() {
+            let (start, stop) = (lower_range[0], lower_range[1]);
+            for (&k, &val) in lower_indices[start..stop]
+                                  .iter()
+                                  .zip(&lower_data[start..stop]) {
+                if k < j {
+                    indices[next[k]] = I::from_usize(j);
+                    data[next[k]] = val;
+                    next[k] += 1;
+                }
+            }
+        }
+        CsMatI {
+            storage: CSR,
+            nrows: n,
+            ncols: n,
+            indptr: indptr.into_iter().map(I::from_usize).collect(),
+            indices: indices,
+            data: data,
+        }
+    }
+
+    /// Test whether this matrix and `other` represent the same mathematical
+    /// matrix, regardless of their storage order or of the explicit zeros
+    /// they may store.
+    ///
+    /// Contrary to `==`, which compares the underlying arrays, this has
+    /// to iterate over the non-zeros of both matrices, and is thus
+    /// O(nnz). It also needs to convert `other` if its storage differs
+    /// from this matrix' storage.
+    pub fn logical_eq<IpS2, IS2, DS2>(&self,
+                                      other: &CsMatBase<N, I, IpS2, IS2, DS2>
+                                     ) -> bool
+    where N: Num + Clone,
+          IpS2: Deref<Target=[I]>,
+          IS2: Deref<Target=[I]>,
+          DS2: Deref<Target=[N]>
+    {
+        if self.shape() != other.shape() {
+            return false;
+        }
+        if self.storage != other.storage() {
+            return self.logical_eq(&other.to_other_storage());
+        }
+        self.outer_iterator().zip(other.outer_iterator()).all(|(lv, rv)| {
+            lv.iter().nnz_or_zip(rv.iter()).all(|elem| match elem {
+                Left((_, val)) | Right((_, val)) => val.is_zero(),
+                Both((_, lval, rval)) => lval == rval,
+            })
+        })
+    }
+
+    /// Test whether this matrix and `other` have the same shape and
+    /// elements differing by at most `tol` in absolute value. Elements
+    /// stored in only one of the matrices are compared to zero.
+    ///
+    /// This is a more robust comparison than `==` for floating point
+    /// matrices produced by arithmetic operations.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sprs::CsMat;
+    /// let a = CsMat::new((2, 2), vec![0, 1, 2], vec![0, 1], vec![1., 2.]);
+    /// let b = CsMat::new((2, 2), vec![0, 2, 3], vec![0, 1, 1],
+    ///                    vec![1. + 1e-12, 1e-12, 2.]);
+    /// assert!(a != b);
+    /// assert!(a.approx_eq(&b, 1e-10));
+    /// ```
+    pub fn approx_eq<IpS2, IS2, DS2>(&self,
+                                     other: &CsMatBase<N, I, IpS2, IS2, DS2>,
+                                     tol: N
+                                    ) -> bool
+    where N: Signed + PartialOrd + Copy,
+          IpS2: Deref<Target=[I]>,
+          IS2: Deref<Target=[I]>,
+          DS2: Deref<Target=[N]>
+    {
+        if self.shape() != other.shape() {
+            return false;
+        }
+        if self.storage != other.storage() {
+            return self.approx_eq(&other.to_other_storage(), tol);
+        }
+        self.outer_iterator().zip(other.outer_iterator()).all(|(lv, rv)| {
+            lv.iter().nnz_or_zip(rv.iter()).all(|elem| match elem {
+                Left((_, &val)) | Right((_, &val)) => val.abs() <= tol,
+                Both((_, &lval, &rval)) => (lval - rval).abs() <= tol,
+            })
+        })
+    }
+
 }
 
-sparse_scalar_mul!(u32);
-sparse_scalar_mul!(i32);
-sparse_scalar_mul!(u64);
-sparse_scalar_mul!(i64);
-sparse_scalar_mul!(isize);
-sparse_scalar_mul!(usize);
-sparse_scalar_mul!(f32);
-sparse_scalar_mul!(f64);
+/// # Methods for sparse matrices holding mutable access to their values.
+impl<N, I, IptrStorage, IndStorage, DataStorage>
+CsMatBase<N, I, IptrStorage, IndStorage, DataStorage>
+where
+I: SpIndex,
+IptrStorage: Deref<Target=[I]>,
+IndStorage: Deref<Target=[I]>,
+DataStorage: DerefMut<Target=[N]> {
+
+    /// Mutable access to the non zero values
+    ///
+    /// This enables changing the values without changing the matrix's
+    /// structure. To also change the matrix's structure,
+    /// see [modify](fn.modify.html)
+    pub fn data_mut(&mut self) -> &mut [N] {
+        &mut self.data[..]
+    }
+
+    /// Sparse matrix self-multiplication by a scalar
+    pub fn scale(&mut self, val: N) where N: Num + Copy {
+        for data in self.data_mut() {
+            *data = *data * val;
+        }
+    }
+
+    /// Get a mutable view into the i-th outer dimension
+    /// (eg i-th row for a CSR matrix)
+    pub fn outer_view_mut(&mut self, i: usize) -> Option<CsVecViewMutI<N, I>> {
+        if i >= self.outer_dims() {
+            return None;
+        }
+        let start = self.indptr[i].index();
+        let stop = self.indptr[i+1].index();
+        // CsMat invariants imply CsVec invariants
+        Some(CsVecBase {
+            dim: self.inner_dims(),
+            indices: &self.indices[start..stop],
+            data: &mut self.data[start..stop],
+        })
+    }
+
+    /// Get a mutable reference to the element located at row i and column j.
+    /// Will return None if there is no non-zero element at this location.
+    ///
+    /// This access is logarithmic in the number of non-zeros
+    /// in the corresponding outer slice. It is therefore advisable not to rely
+    /// on this for algorithms, and prefer outer_iterator_mut() which accesses
+    /// elements in storage order.
+    pub fn get_mut(&mut self, i: usize, j: usize) -> Option<&mut N> {
+        match self.storage {
+            CSR => self.get_outer_inner_mut(i, j),
+            CSC => self.get_outer_inner_mut(j, i)
+        }
+    }
+
+    /// Get a mutable reference to an element given its outer_ind and inner_ind.
+    /// Will return None if there is no non-zero element at this location.
+    ///
+    /// This access is logarithmic in the number of non-zeros
+    /// in the corresponding outer slice. It is therefore advisable not to rely
+    /// on this for algorithms, and prefer outer_iterator_mut() which accesses
+    /// elements in storage order.
+    pub fn get_outer_inner_mut(&mut self,
+                              outer_ind: usize,
+                              inner_ind: usize
+                             ) -> Option<&mut N> {
+        if let Some(NnzIndex(index)) = self.nnz_index_outer_inner(outer_ind,
+                                                                  inner_ind) {
+            Some(&mut self.data[index])
+        }
+        else {
+            None
+        }
+    }
+
+    /// Set the value of the non-zero element located at (row, col)
+    ///
+    /// # Panics
+    ///
+    /// - on out-of-bounds access
+    /// - if no non-zero element exists at the given location
+    pub fn set(&mut self, row: usize, col: usize, val: N) {
+        let outer = outer_dimension(self.storage(), row, col);
+        let inner = inner_dimension(self.storage(), row, col);
+        let vec::NnzIndex(index) = self.outer_view(outer).and_then(|vec| {
+            vec.nnz_index(inner)
+        }).unwrap();
+        self.data[index] = val;
+    }
+
+    /// Apply a function to every non-zero element
+    pub fn map_inplace<F>(&mut self, mut f: F)
+    where F: FnMut(&N) -> N
+    {
+        for val in &mut self.data[..] {
+            *val = f(val);
+        }
+    }
+
+    /// Scale each outer dimension so that its elements sum to one (eg to
+    /// get a row-stochastic matrix from a CSR matrix).
+    ///
+    /// Outer dimensions whose elements sum to zero are left untouched.
+    pub fn normalize_outer(&mut self)
+    where N: Num + Copy
+    {
+        for outer in 0..self.outer_dims() {
+            let start = self.indptr[outer].index();
+            let stop = self.indptr[outer + 1].index();
+            let data = &mut self.data[start..stop];
+            let sum = data.iter().fold(N::zero(), |acc, &x| acc + x);
+            if sum == N::zero() {
+                continue;
+            }
+            for val in data.iter_mut() {
+                *val = *val / sum;
+            }
+        }
+    }
+
+    /// Return a mutable outer iterator for the matrix
+    ///
+    /// This iterator yields mutable sparse vector views for each outer
+    /// dimension. Only the non-zero values can be modified, the
+    /// structure is kept immutable.
+    ///
+    /// The outer index can be obtained with `enumerate`, eg to scale each
+    /// row of a CSR matrix by its own factor:
+    ///
+    /// ```rust
+    /// use sprs::CsMat;
+    /// let mut mat = CsMat::new((2, 2), vec![0, 1, 3], vec![0, 0, 1],
+    ///                          vec![1., 1., 2.]);
+    /// let factors = [3., 0.5];
+    /// for (row, mut vec) in mat.outer_iterator_mut().enumerate() {
+    ///     vec.scale(factors[row]);
+    /// }
+    /// assert_eq!(mat.data(), &[3., 0.5, 1.]);
+    /// ```
+    pub fn outer_iterator_mut<'a>(&'a mut self) -> OuterIteratorMut<'a, N, I> {
+        let inner_len = match self.storage {
+            CSR => self.ncols,
+            CSC => self.nrows
+        };
+        OuterIteratorMut {
+            inner_len: inner_len,
+            indptr_iter: self.indptr.windows(2),
+            indices: &self.indices[..],
+            data: &mut self.data[..],
+        }
+    }
+
+}
+
+impl<N, I, IptrStorage, IndStorage, DataStorage>
+CsMatBase<N, I, IptrStorage, IndStorage, DataStorage>
+where
+I: SpIndex,
+IptrStorage: DerefMut<Target=[I]>,
+IndStorage: DerefMut<Target=[I]>,
+DataStorage: DerefMut<Target=[N]> {
+    /// Modify the matrix's structure without changing its nonzero count.
+    ///
+    /// The coherence of the structure will be checked afterwards.
+    ///
+    /// # Panics
+    ///
+    /// If the resulting matrix breaks the CsMat invariants (sorted indices,
+    /// no out of bounds indices).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sprs::CsMat;
+    /// // |   1   |
+    /// // | 1     |
+    /// // |   1 1 |
+    /// let mut mat = CsMat::new_csc((3, 3),
+    ///                                   vec![0, 1, 3, 4],
+    ///                                   vec![1, 0, 2, 2],
+    ///                                   vec![1.; 4]);
+    ///
+    /// // | 1 2   |
+    /// // | 1     |
+    /// // |   1   |
+    /// mat.modify(|indptr, indices, data| {
+    ///     indptr[1] = 2;
+    ///     indptr[2] = 4;
+    ///     indices[0] = 0;
+    ///     indices[1] = 1;
+    ///     indices[2] = 0;
+    ///     data[2] = 2.;
+    /// });
+    /// ```
+    pub fn modify<F>(&mut self, mut f: F)
+    where F: FnMut(&mut [I], &mut [I], &mut [N]) {
+        f(&mut self.indptr[..], &mut self.indices[..], &mut self.data[..]);
+        self.check_compressed_structure().unwrap();
+    }
+}
+
+/// Raw functions acting directly on the compressed structure.
+pub mod raw {
+    use sparse::prelude::*;
+    use indexing::SpIndex;
+    use ::Shape;
+    use std::mem::swap;
+
+    /// Copy-convert a compressed matrix into the oppposite storage.
+    ///
+    /// The input compressed matrix does not need to have its indices sorted,
+    /// but the output compressed matrix will have its indices sorted.
+    ///
+    /// Can be used to implement CSC <-> CSR conversions, or to implement
+    /// same-storage (copy) transposition.
+    ///
+    /// # Panics
+    ///
+    /// Panics if indptr contains non-zero values
+    ///
+    /// Panics if the output slices don't match the input matrices'
+    /// corresponding slices.
+    pub fn convert_storage<N, I>(in_storage: super::CompressedStorage,
+                                 shape: Shape,
+                                 in_indtpr: &[I],
+                                 in_indices: &[I],
+                                 in_data: &[N],
+                                 indptr: &mut [I],
+                                 indices: &mut[I],
+                                 data: &mut [N])
+    where N: Clone, I: SpIndex {
+        // we're building a csmat even though the indices are not sorted,
+        // but it's not a problem since we don't rely on this property.
+        // FIXME: this would be better with an explicit unsorted matrix type
+        let mat = CsMatBase {
+            storage: in_storage,
+            nrows: shape.0,
+            ncols: shape.1,
+            indptr: in_indtpr,
+            indices: in_indices,
+            data: in_data,
+        };
+
+        convert_mat_storage(mat, indptr, indices, data);
+    }
+
+    /// Copy-convert a csmat into the oppposite storage.
+    ///
+    /// Can be used to implement CSC <-> CSR conversions, or to implement
+    /// same-storage (copy) transposition.
+    ///
+    /// # Panics
+    ///
+    /// Panics if indptr contains non-zero values
+    ///
+    /// Panics if the output slices don't match the input matrices'
+    /// corresponding slices.
+    pub fn convert_mat_storage<N: Clone, I: SpIndex>(mat: CsMatViewI<N, I>,
+                                                     indptr: &mut [I],
+                                                     indices: &mut[I],
+                                                     data: &mut [N]) {
+        assert_eq!(indptr.len(), mat.inner_dims() + 1);
+        assert_eq!(indices.len(), mat.indices().len());
+        assert_eq!(data.len(), mat.data().len());
+
+        assert!(indptr.iter().all(|x| *x == I::zero()));
+
+        for vec in mat.outer_iterator() {
+            for (inner_dim, _) in vec.iter() {
+                indptr[inner_dim] += I::one();
+            }
+        }
+
+        let mut cumsum = I::zero();
+        for iptr in indptr.iter_mut() {
+            let tmp = *iptr;
+            *iptr = cumsum;
+            cumsum += tmp;
+        }
+        if let Some(last_iptr) = indptr.last() {
+            assert_eq!(last_iptr.index(), mat.nnz());
+        }
+
+        for (outer_dim, vec) in mat.outer_iterator().enumerate() {
+            for (inner_dim, val) in vec.iter() {
+                let dest = indptr[inner_dim].index();
+                data[dest] = val.clone();
+                indices[dest] = I::from_usize(outer_dim);
+                indptr[inner_dim] += I::one();
+            }
+        }
+
+        let mut last = I::zero();
+        for iptr in indptr.iter_mut() {
+            swap(iptr, &mut last);
+        }
+    }
+}
+
+impl<'a, N:'a, I: 'a + SpIndex> CsMatBase<N, I, Vec<I>, &'a [I], &'a [N]> {
+    /// Create a borrowed row or column CsMat matrix from raw data,
+    /// without checking their validity
+    ///
+    /// This is unsafe because algorithms are free to assume
+    /// that properties guaranteed by check_compressed_structure are enforced.
+    /// For instance, non out-of-bounds indices can be relied upon to
+    /// perform unchecked slice access.
+    pub unsafe fn new_vecview_raw(
+        storage: CompressedStorage, nrows : usize, ncols: usize,
+        indptr : *const I, indices : *const I, data : *const N
+        )
+    -> CsMatVecView_<'a, N, I> {
+        let indptr = slice::from_raw_parts(indptr, 2);
+        let nnz = indptr[1].index();
+        CsMatVecView_ {
+            storage: storage,
+            nrows : nrows,
+            ncols: ncols,
+            indptr : Array2 {data: [indptr[0], indptr[1]]},
+            indices : slice::from_raw_parts(indices, nnz),
+            data : slice::from_raw_parts(data, nnz),
+        }
+    }
+}
+
+/// Sparse matrix addition. The right-hand side is converted to the storage
+/// of the left-hand side if their storages differ, which costs an
+/// additional `O(nnz + outer_dims)` allocation and pass over its elements.
+impl<'a, 'b, N, I, IpStorage, IStorage, DStorage, IpS2, IS2, DS2>
+Add<&'b CsMatBase<N, I, IpS2, IS2, DS2>>
+for &'a CsMatBase<N, I, IpStorage, IStorage, DStorage>
+where N: 'a + Copy + Num + Default,
+      I: 'a + SpIndex,
+      IpStorage: 'a + Deref<Target=[I]>,
+      IStorage: 'a + Deref<Target=[I]>,
+      DStorage: 'a + Deref<Target=[N]>,
+      IpS2: 'a + Deref<Target=[I]>,
+      IS2: 'a + Deref<Target=[I]>,
+      DS2: 'a + Deref<Target=[N]> {
+    type Output = CsMatI<N, I>;
+
+    fn add(self, rhs: &'b CsMatBase<N, I, IpS2, IS2, DS2>) -> CsMatI<N, I> {
+        if self.storage() != rhs.view().storage() {
+            return binop::add_mat_same_storage(
+                self, &rhs.view().to_other_storage())
+        }
+        binop::add_mat_same_storage(self, rhs)
+    }
+}
+
+/// Sparse matrix subtraction. As for addition, the right-hand side is
+/// converted to the storage of the left-hand side if their storages differ.
+impl<'a, 'b, N, I, IpStorage, IStorage, DStorage, Mat> Sub<&'b Mat>
+for &'a CsMatBase<N, I, IpStorage, IStorage, DStorage>
+where N: 'a + Copy + Num + Default,
+      I: 'a + SpIndex,
+      IpStorage: 'a + Deref<Target=[I]>,
+      IStorage: 'a + Deref<Target=[I]>,
+      DStorage: 'a + Deref<Target=[N]>,
+      Mat: SpMatView<N, I> {
+    type Output = CsMatI<N, I>;
+
+    fn sub(self, rhs: &'b Mat) -> CsMatI<N, I> {
+        if self.storage() != rhs.view().storage() {
+            return binop::sub_mat_same_storage(
+                self, &rhs.view().to_other_storage())
+        }
+        binop::sub_mat_same_storage(self, rhs)
+    }
+}
+
+macro_rules! sparse_scalar_mul {
+    ($scalar: ident) => (
+        impl<'a, I, IpStorage, IStorage, DStorage> Mul<$scalar>
+        for &'a CsMatBase<$scalar, I, IpStorage, IStorage, DStorage>
+        where I: 'a + SpIndex,
+              IpStorage: 'a + Deref<Target=[I]>,
+              IStorage: 'a + Deref<Target=[I]>,
+              DStorage: 'a + Deref<Target=[$scalar]> {
+            type Output = CsMatI<$scalar, I>;
+
+            fn mul(self, rhs: $scalar) -> CsMatI<$scalar, I> {
+                binop::scalar_mul_mat(self, rhs)
+            }
+        }
+    )
+}
+
+sparse_scalar_mul!(u32);
+sparse_scalar_mul!(i32);
+sparse_scalar_mul!(u64);
+sparse_scalar_mul!(i64);
+sparse_scalar_mul!(isize);
+sparse_scalar_mul!(usize);
+sparse_scalar_mul!(f32);
+sparse_scalar_mul!(f64);
+
+impl<'a, 'b, N, I, IpS1, IS1, DS1, IpS2, IS2, DS2>
+Mul<&'b CsMatBase<N, I, IpS2, IS2, DS2>>
+for &'a CsMatBase<N, I, IpS1, IS1, DS1>
+where N: 'a + Copy + Num + Default,
+      I: 'a + SpIndex,
+      IpS1: 'a + Deref<Target=[I]>,
+      IS1: 'a + Deref<Target=[I]>,
+      DS1: 'a + Deref<Target=[N]>,
+      IpS2: 'b + Deref<Target=[I]>,
+      IS2: 'b + Deref<Target=[I]>,
+      DS2: 'b + Deref<Target=[N]> {
+    type Output = CsMatI<N, I>;
+
+    fn mul(self, rhs: &'b CsMatBase<N, I, IpS2, IS2, DS2>) -> CsMatI<N, I> {
+        match (self.storage(), rhs.storage()) {
+            (CSR, CSR) => {
+                let mut workspace = prod::workspace_csr(self, rhs);
+                prod::csr_mul_csr(self, rhs, &mut workspace)
+            }
+            (CSR, CSC) => {
+                let mut workspace = prod::workspace_csr(self, rhs);
+                prod::csr_mul_csr(self,
+                                  &rhs.to_other_storage(),
+                                  &mut workspace)
+            }
+            (CSC, CSR) => {
+                let mut workspace = prod::workspace_csc(self, rhs);
+                prod::csc_mul_csc(self, &rhs.to_other_storage(),
+                                  &mut workspace)
+            }
+            (CSC, CSC) => {
+                let mut workspace = prod::workspace_csc(self, rhs);
+                prod::csc_mul_csc(self, rhs, &mut workspace)
+            }
+        }
+    }
+}
+
+impl<'a, 'b, N, I, IpS, IS, DS, DS2>
+Add<&'b ArrayBase<DS2, Ix2>>
+for &'a CsMatBase<N, I, IpS, IS, DS>
+where N: 'a + Copy + Num + Default,
+      I: 'a + SpIndex,
+      IpS: 'a + Deref<Target=[I]>,
+      IS: 'a + Deref<Target=[I]>,
+      DS: 'a + Deref<Target=[N]>,
+      DS2: 'b + ndarray::Data<Elem=N> {
+    type Output = Array<N, Ix2>;
+
+    fn add(self, rhs: &'b ArrayBase<DS2, Ix2>) -> Array<N, Ix2> {
+        match (self.storage(), rhs.is_standard_layout()) {
+            (CSR, true) => {
+                    binop::add_dense_mat_same_ordering(self,
+                                                       rhs,
+                                                       N::one(),
+                                                       N::one()
+                                                      )
+                }
+                (CSR, false) => {
+                    let lhs = self.to_other_storage();
+                    binop::add_dense_mat_same_ordering(&lhs,
+                                                       rhs,
+                                                       N::one(),
+                                                       N::one()
+                                                      )
+                }
+                (CSC, true) => {
+                    let lhs = self.to_other_storage();
+                    binop::add_dense_mat_same_ordering(&lhs,
+                                                       rhs,
+                                                       N::one(),
+                                                       N::one()
+                                                      )
+                }
+                (CSC, false) => {
+                    binop::add_dense_mat_same_ordering(self,
+                                                       rhs,
+                                                       N::one(),
+                                                       N::one()
+                                                      )
+                }
+        }
+    }
+}
+
+impl<'a, 'b, N, I, IpS, IS, DS, DS2>
+Mul<&'b ArrayBase<DS2, Ix2>>
+for &'a CsMatBase<N, I, IpS, IS, DS>
+where N: 'a + Copy + Num + Default,
+      I: 'a + SpIndex,
+      IpS: 'a + Deref<Target=[I]>,
+      IS: 'a + Deref<Target=[I]>,
+      DS: 'a + Deref<Target=[N]>,
+      DS2: 'b + ndarray::Data<Elem=N> {
+    type Output = Array<N, Ix2>;
+
+    fn mul(self, rhs: &'b ArrayBase<DS2, Ix2>) -> Array<N, Ix2> {
+        let rows = self.rows();
+        let cols = rhs.shape()[1];
+        // when the number of colums is small, it is more efficient
+        // to perform the product by iterating over the columns of
+        // the rhs, otherwise iterating by rows can take advantage of
+        // vectorized axpy.
+        match (self.storage(), cols >= 8) {
+            (CSR, true) => {
+                let mut res = Array::zeros((rows, cols));
+                prod::csr_mulacc_dense_rowmaj(self.view(),
+                                              rhs.view(),
+                                              res.view_mut()
+                                             );
+                res
+            }
+            (CSR, false) => {
+                let mut res = Array::zeros((rows, cols).f());
+                prod::csr_mulacc_dense_colmaj(self.view(),
+                                              rhs.view(),
+                                              res.view_mut()
+                                             );
+                res
+            }
+            (CSC, true) => {
+                let mut res = Array::zeros((rows, cols));
+                prod::csc_mulacc_dense_rowmaj(self.view(),
+                                              rhs.view(),
+                                              res.view_mut()
+                                             );
+                res
+            }
+            (CSC, false) => {
+                let mut res = Array::zeros((rows, cols).f());
+                prod::csc_mulacc_dense_colmaj(self.view(),
+                                              rhs.view(),
+                                              res.view_mut()
+                                             );
+                res
+            }
+        }
+    }
+}
+
+impl<'a, 'b, N, I, IpS, IS, DS, DS2>
+Mul<&'b ArrayBase<DS2, Ix1>>
+for &'a CsMatBase<N, I, IpS, IS, DS>
+where N: 'a + Copy + Num + Default,
+      I: 'a + SpIndex,
+      IpS: 'a + Deref<Target=[I]>,
+      IS: 'a + Deref<Target=[I]>,
+      DS: 'a + Deref<Target=[N]>,
+      DS2: 'b + ndarray::Data<Elem=N> {
+    type Output = Array<N, Ix1>;
+
+    fn mul(self, rhs: &'b ArrayBase<DS2, Ix1>) -> Array<N, Ix1> {
+        let rows = self.rows();
+        let cols = rhs.shape()[0];
+        let rhs_reshape = rhs.view().into_shape((cols, 1)).unwrap();
+        let mut res = Array::zeros(rows);
+        {
+            let res_reshape = res.view_mut().into_shape((rows, 1)).unwrap();
+            match self.storage() {
+                CSR => {
+                    prod::csr_mulacc_dense_colmaj(self.view(),
+                                                  rhs_reshape,
+                                                  res_reshape
+                                                 );
+                }
+                CSC => {
+                    prod::csc_mulacc_dense_colmaj(self.view(),
+                                                  rhs_reshape,
+                                                  res_reshape
+                                                 );
+                }
+            }
+        }
+        res
+    }
+}
+
+impl<N: Num + Copy + Neg<Output=N>, I: SpIndex> Neg for CsMatI<N, I> {
+    type Output = CsMatI<N, I>;
+
+    fn neg(mut self) -> CsMatI<N, I> {
+        for value in &mut self.data {
+            *value = -*value;
+        }
+        self
+    }
+}
+
+impl<N, I, IpS, IS, DS> Index<[usize; 2]> for CsMatBase<N, I, IpS, IS, DS>
+where I: SpIndex,
+      IpS: Deref<Target=[I]>,
+      IS: Deref<Target=[I]>,
+      DS: Deref<Target=[N]>
+{
+    type Output = N;
+
+    fn index(&self, index: [usize; 2]) -> &N {
+        let i = index[0];
+        let j = index[1];
+        self.get(i, j).unwrap()
+    }
+}
+
+impl<N, I, IpS, IS, DS> IndexMut<[usize; 2]> for CsMatBase<N, I, IpS, IS, DS>
+where I: SpIndex,
+      IpS: Deref<Target=[I]>,
+      IS: Deref<Target=[I]>,
+      DS: DerefMut<Target=[N]>
+{
+    fn index_mut(&mut self, index: [usize; 2]) -> &mut N {
+        let i = index[0];
+        let j = index[1];
+        self.get_mut(i, j).unwrap()
+    }
+}
+
+impl<N, I, IpS, IS, DS> Index<(usize, usize)> for CsMatBase<N, I, IpS, IS, DS>
+where I: SpIndex,
+      IpS: Deref<Target=[I]>,
+      IS: Deref<Target=[I]>,
+      DS: Deref<Target=[N]>
+{
+    type Output = N;
+
+    fn index(&self, (i, j): (usize, usize)) -> &N {
+        self.get(i, j).unwrap()
+    }
+}
+
+impl<N, I, IpS, IS, DS> IndexMut<(usize, usize)> for CsMatBase<N, I, IpS, IS, DS>
+where I: SpIndex,
+      IpS: Deref<Target=[I]>,
+      IS: Deref<Target=[I]>,
+      DS: DerefMut<Target=[N]>
+{
+    fn index_mut(&mut self, (i, j): (usize, usize)) -> &mut N {
+        self.get_mut(i, j).unwrap()
+    }
+}
+
+
+impl<N, I, IpS, IS, DS> Index<NnzIndex> for CsMatBase<N, I, IpS, IS, DS>
+where I: SpIndex,
+      IpS: Deref<Target=[I]>,
+      IS: Deref<Target=[I]>,
+      DS: Deref<Target=[N]>
+{
+    type Output = N;
+
+    fn index(&self, index: NnzIndex) -> &N {
+        let NnzIndex(i) = index;
+        self.data().get(i).unwrap()
+    }
+}
+
+impl<N, I, IpS, IS, DS> IndexMut<NnzIndex> for CsMatBase<N, I, IpS, IS, DS>
+where I: SpIndex,
+      IpS: Deref<Target=[I]>,
+      IS: Deref<Target=[I]>,
+      DS: DerefMut<Target=[N]>
+{
+    fn index_mut(&mut self, index: NnzIndex) -> &mut N {
+        let NnzIndex(i) = index;
+        self.data_mut().get_mut(i).unwrap()
+    }
+}
+
+impl<N, I, IpS, IS, DS> SparseMat for CsMatBase<N, I, IpS, IS, DS>
+where I: SpIndex,
+      IpS: Deref<Target=[I]>,
+      IS: Deref<Target=[I]>,
+      DS: Deref<Target=[N]>,
+{
+    fn rows(&self) -> usize {
+        self.rows()
+    }
+
+    fn cols(&self) -> usize {
+        self.cols()
+    }
+
+    fn nnz(&self) -> usize {
+        self.nnz()
+    }
+}
+
+impl<'a, N, I, IpS, IS, DS> SparseMat for &'a CsMatBase<N, I, IpS, IS, DS>
+where I: 'a + SpIndex,
+      N: 'a,
+      IpS: Deref<Target=[I]>,
+      IS: Deref<Target=[I]>,
+      DS: Deref<Target=[N]>,
+{
+    fn rows(&self) -> usize {
+        (*self).rows()
+    }
+
+    fn cols(&self) -> usize {
+        (*self).cols()
+    }
+
+    fn nnz(&self) -> usize {
+        (*self).nnz()
+    }
+}
+
+impl<'a, N, I, IpS, IS, DS> IntoIterator for &'a CsMatBase<N, I, IpS, IS, DS>
+where I: 'a + SpIndex,
+      N: 'a,
+      IpS: Deref<Target=[I]>,
+      IS: Deref<Target=[I]>,
+      DS: Deref<Target=[N]>,
+{
+    type Item = (&'a N, (I, I));
+    type IntoIter = CsIter<'a, N, I>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Consume an owned matrix, iterating over its non-zero elements as
+/// `(row, col, value)` triplets without cloning the values.
+///
+/// # Example
+///
+/// ```rust
+/// use sprs::CsMat;
+/// let mat = CsMat::new((2, 2), vec![0, 1, 2], vec![1, 0], vec![1., 2.]);
+/// let triplets: Vec<_> = mat.into_iter().collect();
+/// assert_eq!(triplets, vec![(0, 1, 1.), (1, 0, 2.)]);
+/// ```
+impl<N, I: SpIndex> IntoIterator for CsMatI<N, I> {
+    type Item = (usize, usize, N);
+    type IntoIter = CsIntoIter<N, I>;
+    fn into_iter(self) -> Self::IntoIter {
+        CsIntoIter {
+            storage: self.storage,
+            cur_outer: 0,
+            indptr: self.indptr,
+            inner_iter: self.indices.into_iter()
+                                    .zip(self.data.into_iter())
+                                    .enumerate(),
+        }
+    }
+}
+
+impl<'a, N, I> IntoIterator for CsMatViewI<'a, N, I>
+where I: 'a + SpIndex,
+      N: 'a,
+{
+    type Item = (&'a N, (I, I));
+    type IntoIter = CsIter<'a, N, I>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_rbr()
+    }
+}
+
+fn max_or_zero<N: Num + PartialOrd + Copy>(vals: &[N]) -> N {
+    vals.iter().fold(N::zero(), |max, &x| if x > max { x } else { max })
+}
+
+/// Maximum number of non-zero elements displayed by the `Debug`
+/// implementation of `CsMatBase`
+const DEBUG_MAX_ENTRIES: usize = 10;
+
+impl<N, I, IpS, IS, DS> fmt::Debug for CsMatBase<N, I, IpS, IS, DS>
+where N: fmt::Debug,
+      I: SpIndex,
+      IpS: Deref<Target=[I]>,
+      IS: Deref<Target=[I]>,
+      DS: Deref<Target=[N]>
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f,
+               "CsMatBase {{ storage: {:?}, nrows: {}, ncols: {}, \
+                nnz: {}, entries: [",
+               self.storage, self.nrows, self.ncols, self.nnz())?;
+        for (count, (val, (i, j))) in self.iter().enumerate() {
+            if count == DEBUG_MAX_ENTRIES {
+                write!(f, ", ...")?;
+                break;
+            }
+            if count > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "({:?}, {:?}): {:?}", i, j, val)?;
+        }
+        write!(f, "] }}")
+    }
+}
+
+/// Maximum number of rows or columns of a matrix displayed as a dense grid
+/// by the `Display` implementation of `CsMatBase`
+const DISPLAY_MAX_DIM: usize = 20;
+
+/// Small matrices are displayed as a dense grid, with zero elements shown
+/// as `.`, while larger matrices are summarized by their shape, storage and
+/// number of non-zeros.
+impl<N, I, IpS, IS, DS> fmt::Display for CsMatBase<N, I, IpS, IS, DS>
+where N: fmt::Display + Zero + PartialEq,
+      I: SpIndex,
+      IpS: Deref<Target=[I]>,
+      IS: Deref<Target=[I]>,
+      DS: Deref<Target=[N]>
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.nrows > DISPLAY_MAX_DIM || self.ncols > DISPLAY_MAX_DIM {
+            return write!(f, "{}x{} {:?} matrix with {} non-zeros",
+                          self.nrows, self.ncols, self.storage, self.nnz());
+        }
+        let mut cells = vec![String::from("."); self.nrows * self.ncols];
+        for (val, (i, j)) in self.iter() {
+            if *val != N::zero() {
+                cells[i.index() * self.ncols + j.index()] = val.to_string();
+            }
+        }
+        let width = cells.iter().map(|cell| cell.len()).max().unwrap_or(0);
+        for i in 0..self.nrows {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            for j in 0..self.ncols {
+                if j > 0 {
+                    write!(f, " ")?;
+                }
+                let cell = &cells[i * self.ncols + j];
+                write!(f, "{:>width$}", cell, width = width)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// An iterator over non-overlapping blocks of a matrix,
+/// along the least-varying dimension
+pub struct ChunkOuterBlocks<'a, N: 'a, I: 'a + SpIndex> {
+    mat: CsMatViewI<'a, N, I>,
+    dims_in_bloc: usize,
+    bloc_count: usize,
+}
+
+impl<'a, N: 'a, I: 'a + SpIndex> Iterator for ChunkOuterBlocks<'a, N, I> {
+    type Item = CsMatViewI<'a, N, I>;
+    fn next(&mut self) -> Option<<Self as Iterator>::Item> {
+        let cur_dim = self.dims_in_bloc * self.bloc_count;
+        let end_dim = self.dims_in_bloc + cur_dim;
+        let count = if self.dims_in_bloc == 0 {
+            return None;
+        }
+        else if end_dim > self.mat.outer_dims() {
+            let count = self.mat.outer_dims() - cur_dim;
+            self.dims_in_bloc = 0;
+            count
+        }
+        else {
+            self.dims_in_bloc
+        };
+        let view = self.mat.middle_outer_views(cur_dim, count);
+        self.bloc_count += 1;
+        Some(view)
+    }
+}
+
+
+#[cfg(feature = "serde")]
+mod serde_impls {
+    use super::*;
+    use serde::{Serializer, Deserializer};
+    use serde::de::Error;
+
+    #[derive(Serialize)]
+    struct CsMatRef<'a, N: 'a, I: 'a> {
+        storage: CompressedStorage,
+        nrows: usize,
+        ncols: usize,
+        indptr: &'a [I],
+        indices: &'a [I],
+        data: &'a [N],
+    }
+
+    #[derive(Deserialize)]
+    struct CsMatRepr<N, I> {
+        storage: CompressedStorage,
+        nrows: usize,
+        ncols: usize,
+        indptr: Vec<I>,
+        indices: Vec<I>,
+        data: Vec<N>,
+    }
+
+    impl<N, I, IpS, IS, DS> Serialize for CsMatBase<N, I, IpS, IS, DS>
+    where N: Serialize,
+          I: SpIndex + Serialize,
+          IpS: Deref<Target=[I]>,
+          IS: Deref<Target=[I]>,
+          DS: Deref<Target=[N]>
+    {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where S: Serializer
+        {
+            let repr = CsMatRef {
+                storage: self.storage,
+                nrows: self.nrows,
+                ncols: self.ncols,
+                indptr: &self.indptr[..],
+                indices: &self.indices[..],
+                data: &self.data[..],
+            };
+            repr.serialize(serializer)
+        }
+    }
+
+    /// Deserializing a matrix checks its structure, and reports a
+    /// deserialization error if it is invalid.
+    impl<'de, N, I> Deserialize<'de> for CsMatI<N, I>
+    where N: Deserialize<'de>,
+          I: SpIndex + Deserialize<'de>
+    {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where D: Deserializer<'de>
+        {
+            let repr = CsMatRepr::deserialize(deserializer)?;
+            let mat = CsMatI {
+                storage: repr.storage,
+                nrows: repr.nrows,
+                ncols: repr.ncols,
+                indptr: repr.indptr,
+                indices: repr.indices,
+                data: repr.data,
+            };
+            match mat.check_compressed_structure() {
+                Ok(()) => Ok(mat),
+                Err(e) => Err(D::Error::custom(e)),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use sparse::{CsMatView, CsMat, CsMatI, CsVecI, CsVecView};
+    use super::CompressedStorage::{CSC, CSR};
+    use errors::SprsError;
+    use test_data::{mat1, mat1_csc, mat1_times_2};
+    use ndarray::{arr1, arr2};
+
+    #[test]
+    fn test_new_csr_success() {
+        let indptr_ok : &[usize] = &[0, 1, 2, 3];
+        let indices_ok : &[usize] = &[0, 1, 2];
+        let data_ok : &[f64] = &[1., 1., 1.];
+        let m = CsMatView::new_view(CSR, (3, 3), indptr_ok, indices_ok, data_ok);
+        assert!(m.is_ok());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_new_csr_bad_indptr_length() {
+        let indptr_fail1 : &[usize] = &[0, 1, 2];
+        let indices_ok : &[usize] = &[0, 1, 2];
+        let data_ok : &[f64] = &[1., 1., 1.];
+        let res = CsMatView::new_view(CSR,
+                                      (3, 3),
+                                      indptr_fail1,
+                                      indices_ok,
+                                      data_ok);
+        res.unwrap(); // unreachable
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_new_csr_out_of_bounds_index() {
+        let indptr_ok : &[usize] = &[0, 1, 2, 3];
+        let data_ok : &[f64] = &[1., 1., 1.];
+        let indices_fail2 : &[usize] = &[0, 1, 4];
+        let res = CsMatView::new_view(CSR,
+                                      (3, 3),
+                                      indptr_ok,
+                                      indices_fail2,
+                                      data_ok);
+        res.unwrap(); //unreachable
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_new_csr_bad_nnz_count() {
+        let indices_ok : &[usize] = &[0, 1, 2];
+        let data_ok : &[f64] = &[1., 1., 1.];
+        let indptr_fail2 : &[usize] = &[0, 1, 2, 4];
+        let res = CsMatView::new_view(CSR,
+                                      (3, 3),
+                                      indptr_fail2,
+                                      indices_ok,
+                                      data_ok);
+        res.unwrap(); //unreachable
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_new_csr_data_indices_mismatch1() {
+        let indptr_ok : &[usize] = &[0, 1, 2, 3];
+        let data_ok : &[f64] = &[1., 1., 1.];
+        let indices_fail1 : &[usize] = &[0, 1];
+        let res = CsMatView::new_view(CSR,
+                                      (3, 3),
+                                      indptr_ok,
+                                      indices_fail1,
+                                      data_ok);
+        res.unwrap(); //unreachable
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_new_csr_data_indices_mismatch2() {
+        let indptr_ok : &[usize] = &[0, 1, 2, 3];
+        let indices_ok : &[usize] = &[0, 1, 2];
+        let data_fail1 : &[f64] = &[1., 1., 1., 1.];
+        let res = CsMatView::new_view(CSR,
+                                      (3, 3),
+                                      indptr_ok,
+                                      indices_ok,
+                                      data_fail1);
+        res.unwrap(); //unreachable
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_new_csr_data_indices_mismatch3() {
+        let indptr_ok : &[usize] = &[0, 1, 2, 3];
+        let indices_ok : &[usize] = &[0, 1, 2];
+        let data_fail2 : &[f64] = &[1., 1.,];
+        let res = CsMatView::new_view(CSR,
+                                      (3, 3),
+                                      indptr_ok,
+                                      indices_ok,
+                                      data_fail2);
+        res.unwrap(); //unreachable
+    }
+
+    #[test]
+    fn test_new_view_structure_errors() {
+        let indptr_ok : &[usize] = &[0, 1, 2, 3];
+        let indices_ok : &[usize] = &[0, 1, 2];
+        let data_ok : &[f64] = &[1., 1., 1.];
+        let res = CsMatView::new_view(CSR, (3, 3), &indptr_ok[..3],
+                                      indices_ok, data_ok);
+        assert_eq!(res, Err(SprsError::BadIndptrLength));
+        let res = CsMatView::new_view(CSR, (3, 3), indptr_ok,
+                                      indices_ok, &data_ok[..2]);
+        assert_eq!(res, Err(SprsError::DataIndicesMismatch));
+        let res = CsMatView::new_view(CSR, (3, 3), &[0, 1, 2, 2],
+                                      indices_ok, data_ok);
+        assert_eq!(res, Err(SprsError::BadNnzCount));
+        let res = CsMatView::new_view(CSR, (3, 3), &[0, 4, 2, 3],
+                                      indices_ok, data_ok);
+        assert_eq!(res, Err(SprsError::OutOfBoundsIndptr));
+        let res = CsMatView::new_view(CSR, (3, 3), &[0, 2, 1, 3],
+                                      indices_ok, data_ok);
+        assert_eq!(res, Err(SprsError::UnsortedIndptr));
+        let res = CsMatView::new_view(CSR, (3, 3), indptr_ok,
+                                      &[0, 1, 3], data_ok);
+        assert_eq!(res, Err(SprsError::OutOfBoundsIndex));
+        let res = CsMatView::new_view(CSR, (3, 3), &[0, 2, 2, 3],
+                                      &[1, 0, 2], data_ok);
+        assert_eq!(res, Err(SprsError::NonSortedIndices));
+    }
+
+    #[test]
+    fn test_new_csr_fails() {
+        let indices_ok : &[usize] = &[0, 1, 2];
+        let data_ok : &[f64] = &[1., 1., 1.];
+        let indptr_fail3 : &[usize] = &[0, 2, 1, 3];
+        assert_eq!(CsMatView::new_view(CSR,
+                                       (3, 3),
+                                       indptr_fail3,
+                                       indices_ok,
+                                       data_ok),
+                   Err(SprsError::UnsortedIndptr));
+    }
+
+    #[test]
+    fn test_new_csr_fail_indices_ordering() {
+        let indptr: &[usize] = &[0, 2, 4, 5, 6, 7];
+        // good indices would be [2, 3, 3, 4, 2, 1, 3];
+        let indices: &[usize] = &[3, 2, 3, 4, 2, 1, 3];
+        let data: &[f64] = &[
+            0.35310881, 0.42380633, 0.28035896, 0.58082095,
+            0.53350123, 0.88132896, 0.72527863];
+        assert_eq!(CsMatView::new_view(CSR,
+                                       (5, 5),
+                                       indptr,
+                                       indices,
+                                       data),
+                   Err(SprsError::NonSortedIndices));
+    }
+
+    #[test]
+    fn test_new_csr_csc_success() {
+        let indptr_ok : &[usize] = &[0, 2, 5, 6];
+        let indices_ok : &[usize] = &[2, 3, 1, 2, 3, 3];
+        let data_ok : &[f64] = &[
+            0.05734571, 0.15543348, 0.75628258,
+            0.83054515, 0.71851547, 0.46202352];
+        assert!(CsMatView::new_view(CSR, (3, 4),
+                                    indptr_ok, indices_ok, data_ok).is_ok());
+        assert!(CsMatView::new_view(CSC, (4, 3),
+                                    indptr_ok, indices_ok, data_ok).is_ok());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_new_csc_bad_indptr_length() {
+        let indptr_ok : &[usize] = &[0, 2, 5, 6];
+        let indices_ok : &[usize] = &[2, 3, 1, 2, 3, 3];
+        let data_ok : &[f64] = &[
+            0.05734571, 0.15543348, 0.75628258,
+            0.83054515, 0.71851547, 0.46202352];
+        let res = CsMatView::new_view(CSC,
+                                      (3, 4),
+                                      indptr_ok,
+                                      indices_ok,
+                                      data_ok);
+        res.unwrap(); //unreachable
+    }
+
+
+    #[test]
+    fn test_new_csr_vec_borrowed() {
+        let indptr_ok = vec![0, 1, 2, 3];
+        let indices_ok = vec![0, 1, 2];
+        let data_ok : Vec<f64> = vec![1., 1., 1.];
+        assert!(CsMatView::new_view(CSR, (3, 3),
+                                    &indptr_ok, &indices_ok, &data_ok).is_ok());
+    }
+
+    #[test]
+    fn test_new_csr_vec_owned() {
+        let indptr_ok = vec![0, 1, 2, 3];
+        let indices_ok = vec![0, 1, 2];
+        let data_ok : Vec<f64> = vec![1., 1., 1.];
+        assert!(CsMat::new_(CSR, (3, 3),
+                                 indptr_ok, indices_ok, data_ok).is_ok());
+    }
+
+    #[test]
+    fn owned_csr_unsorted_indices() {
+        let indptr = vec![0, 3, 3, 5, 6, 7];
+        let indices_sorted = &[1, 2, 3, 2, 3, 4, 4];
+        let indices_shuffled = vec![1, 3, 2, 2, 3, 4, 4];
+        let mut data: Vec<i32> = (0..7).collect();
+        let m = CsMat::new((5, 5), indptr, indices_shuffled, data.clone());
+        assert_eq!(m.indices(), indices_sorted);
+        data.swap(1, 2);
+        assert_eq!(m.data(), &data[..]);
+    }
+
+    #[test]
+    fn new_csr_with_empty_row() {
+        let indptr: &[usize] = &[0, 3, 3, 5, 6, 7];
+        let indices: &[usize] = &[1, 2, 3, 2, 3, 4, 4];
+        let data: &[f64] = &[
+            0.75672424, 0.1649078, 0.30140296, 0.10358244,
+            0.6283315, 0.39244208, 0.57202407];
+        assert!(CsMatView::new_view(CSR,
+                                    (5, 5),
+                                    indptr,
+                                    indices,
+                                    data).is_ok());
+    }
+
+    #[test]
+    fn csr_to_csc() {
+        let a = mat1();
+        let a_csc_ground_truth = mat1_csc();
+        let a_csc = a.to_other_storage();
+        assert_eq!(a_csc, a_csc_ground_truth);
+    }
+
+    #[test]
+    fn test_self_smul() {
+        let mut a = mat1();
+        a.scale(2.);
+        let c_true = mat1_times_2();
+        assert_eq!(a.indptr(), c_true.indptr());
+        assert_eq!(a.indices(), c_true.indices());
+        assert_eq!(a.data(), c_true.data());
+    }
+
+    #[test]
+    fn outer_block_iter() {
+        let mat : CsMat<f64> = CsMat::eye(11);
+        let mut block_iter = mat.outer_block_iter(3);
+        assert_eq!(block_iter.next().unwrap().rows(), 3);
+        assert_eq!(block_iter.next().unwrap().rows(), 3);
+        assert_eq!(block_iter.next().unwrap().rows(), 3);
+        assert_eq!(block_iter.next().unwrap().rows(), 2);
+        assert_eq!(block_iter.next(), None);
+
+        let mut block_iter = mat.outer_block_iter(4);
+        assert_eq!(block_iter.next().unwrap().cols(), 11);
+        block_iter.next().unwrap();
+        block_iter.next().unwrap();
+        assert_eq!(block_iter.next(), None);
+    }
+
+    #[test]
+    fn nnz_index() {
+        let mat : CsMat<f64> = CsMat::eye(11);
+
+        assert_eq!(mat.nnz_index(2, 3), None);
+        assert_eq!(mat.nnz_index(5, 7), None);
+        assert_eq!(mat.nnz_index(0, 11), None);
+        assert_eq!(mat.nnz_index(0, 0), Some(super::NnzIndex(0)));
+        assert_eq!(mat.nnz_index(7, 7), Some(super::NnzIndex(7)));
+        assert_eq!(mat.nnz_index(10, 10), Some(super::NnzIndex(10)));
+
+        let index = mat.nnz_index(8, 8).unwrap();
+        assert_eq!(mat[index], 1.);
+        let mut mat = mat;
+        mat[index] = 2.;
+        assert_eq!(mat[index], 2.);
+    }
+
+    #[test]
+    fn nnz_index_explicit_zero() {
+        let mat = CsMat::new((2, 2), vec![0, 2, 2], vec![0, 1], vec![1., 0.]);
+        assert!(mat.nnz_index(0, 1).is_some());
+        assert_eq!(mat.get(0, 1), Some(&0.));
+        assert!(mat.nnz_index(1, 1).is_none());
+        assert!(mat.to_csc().nnz_index(0, 1).is_some());
+    }
+
+    #[test]
+    fn index() {
+        // | 0 2 0 |
+        // | 1 0 0 |
+        // | 0 3 4 |
+        let mat = CsMat::new_csc((3, 3),
+                                 vec![0, 1, 3, 4],
+                                 vec![1, 0, 2, 2],
+                                 vec![1., 2., 3., 4.]);
+        assert_eq!(mat[[1, 0]], 1.);
+        assert_eq!(mat[[0, 1]], 2.);
+        assert_eq!(mat[[2, 1]], 3.);
+        assert_eq!(mat[[2, 2]], 4.);
+        assert_eq!(mat.get(0, 0), None);
+        assert_eq!(mat.get(4, 4), None);
+    }
+
+    #[test]
+    fn try_get() {
+        let mat = CsMat::new_csc((3, 2),
+                                 vec![0, 1, 3],
+                                 vec![1, 0, 2],
+                                 vec![1., 2., 3.]);
+        assert_eq!(mat.try_get(1, 0), Ok(Some(&1.)));
+        assert_eq!(mat.try_get(2, 1), Ok(Some(&3.)));
+        assert_eq!(mat.try_get(0, 0), Ok(None));
+        assert_eq!(mat.try_get(3, 0), Err(SprsError::OutOfBoundsIndex));
+        assert_eq!(mat.try_get(0, 2), Err(SprsError::OutOfBoundsIndex));
+    }
+
+    #[test]
+    fn index_tuple() {
+        // | 0 2 0 |
+        // | 1 0 0 |
+        // | 0 3 4 |
+        let mut mat = CsMat::new_csc((3, 3),
+                                     vec![0, 1, 3, 4],
+                                     vec![1, 0, 2, 2],
+                                     vec![1., 2., 3., 4.]);
+        assert_eq!(mat[(1, 0)], 1.);
+        assert_eq!(mat[(0, 1)], 2.);
+        assert_eq!(mat[(2, 1)], 3.);
+        mat[(2, 2)] = 5.;
+        assert_eq!(mat[(2, 2)], 5.);
+    }
+
+    #[test]
+    #[should_panic]
+    fn index_tuple_absent() {
+        let mat: CsMat<f64> = CsMat::eye(3);
+        let _val = mat[(0, 1)];
+    }
+
+    #[test]
+    fn get_mut() {
+        // | 0 1 0 |
+        // | 1 0 0 |
+        // | 0 1 1 |
+        let mut mat = CsMat::new_csc((3, 3),
+                                     vec![0, 1, 3, 4],
+                                     vec![1, 0, 2, 2],
+                                     vec![1.; 4]);
+
+        *mat.get_mut(2, 1).unwrap() = 3.;
+
+        let exp = CsMat::new_csc((3, 3),
+                                 vec![0, 1, 3, 4],
+                                 vec![1, 0, 2, 2],
+                                 vec![1., 1., 3., 1.]);
+
+        assert_eq!(mat, exp);
+
+        mat[[2, 2]] = 5.;
+        let exp = CsMat::new_csc((3, 3),
+                                 vec![0, 1, 3, 4],
+                                 vec![1, 0, 2, 2],
+                                 vec![1., 1., 3., 5.]);
+
+        assert_eq!(mat, exp);
+    }
+
+    #[test]
+    fn map() {
+        // | 0 1 0 |
+        // | 1 0 0 |
+        // | 0 1 1 |
+        let mat = CsMat::new_csc((3, 3),
+                                 vec![0, 1, 3, 4],
+                                 vec![1, 0, 2, 2],
+                                 vec![1.; 4]);
+
+        let mut res = mat.map(|&x| x + 2.);
+        let expected = CsMat::new_csc((3, 3),
+                                      vec![0, 1, 3, 4],
+                                      vec![1, 0, 2, 2],
+                                      vec![3.; 4]);
+        assert_eq!(res, expected);
+
+        res.map_inplace(|&x| x / 3.);
+        assert_eq!(res, mat);
+    }
+
+    #[test]
+    fn insert() {
+        // | 0 1 0 |
+        // | 1 0 0 |
+        // | 0 1 1 |
+        let mut mat = CsMat::empty(CSR, 0);
+        mat.reserve_outer_dim(3);
+        mat.reserve_nnz(4);
+        // exercise the fast and easy path where the elements are added
+        // in row order for a CSR matrix
+        mat.insert(0, 1, 1.);
+        mat.insert(1, 0, 1.);
+        mat.insert(2, 1, 1.);
+        mat.insert(2, 2, 1.);
+
+        let expected = CsMat::new((3, 3),
+                                  vec![0, 1, 2, 4],
+                                  vec![1, 0, 1, 2],
+                                  vec![1.; 4]);
+        assert_eq!(mat, expected);
+
+        // | 2 1 0 |
+        // | 1 0 0 |
+        // | 0 1 1 |
+        // exercise adding inside an already formed row (ie a search needs
+        // to be performed)
+        mat.insert(0, 0, 2.);
+        let expected = CsMat::new((3, 3),
+                                  vec![0, 2, 3, 5],
+                                  vec![0, 1, 0, 1, 2],
+                                  vec![2., 1., 1., 1., 1.]);
+        assert_eq!(mat, expected);
+
+        // | 2 1 0 |
+        // | 3 0 0 |
+        // | 0 1 1 |
+        // exercise the fact that inserting in an existing element
+        // should change this element's value
+        mat.insert(1, 0, 3.);
+        let expected = CsMat::new((3, 3),
+                                  vec![0, 2, 3, 5],
+                                  vec![0, 1, 0, 1, 2],
+                                  vec![2., 1., 3., 1., 1.]);
+        assert_eq!(mat, expected);
+    }
+
+    #[test]
+    /// Non-regression test for https://github.com/vbarrielle/sprs/issues/129
+    fn bug_129() {
+        let mut mat = CsMat::zero((3, 100));
+        mat.insert(2, 3, 42);
+        let mut iter = mat.iter();
+        assert_eq!(iter.next(), Some((&42, (2, 3))));
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn iter_mut() {
+        // | 0 1 0 |
+        // | 1 0 0 |
+        // | 0 1 1 |
+        let mut mat = CsMat::new_csc((3, 3),
+                                     vec![0, 1, 3, 4],
+                                     vec![1, 0, 2, 2],
+                                     vec![1.; 4]);
+
+        for mut col_vec in mat.outer_iterator_mut() {
+            for (row_ind, val) in col_vec.iter_mut() {
+                *val = row_ind as f64 + 1.;
+            }
+        }
+
+        let expected = CsMat::new_csc((3, 3),
+                                      vec![0, 1, 3, 4],
+                                      vec![1, 0, 2, 2],
+                                      vec![2., 1., 3., 3.]);
+        assert_eq!(mat, expected);
+    }
+
+    #[test]
+    #[should_panic]
+    fn modify_fail() {
+        let mut mat = CsMat::new_csc((3, 3),
+                                      vec![0, 1, 3, 4],
+                                      vec![1, 0, 2, 2],
+                                      vec![1.; 4]);
+
+        // we panic because we forget to modify the last index, which gets
+        // pushed in the same col as its predecessor, yet has the same value
+        mat.modify(|indptr, indices, data| {
+            indptr[1] = 2;
+            indptr[2] = 4;
+            indices[0] = 0;
+            indices[1] = 1;
+            data[2] = 2.;
+        });
+    }
+
+    #[test]
+    fn convert_types() {
+        let mat: CsMat<f32> = CsMat::eye(3);
+        let mat_: CsMatI<f64, u32> = mat.to_other_types();
+        assert_eq!(mat_.indptr(), &[0, 1, 2, 3]);
+
+        let mat = CsMatI::new_csc((3, 3),
+                                  vec![0u32, 1, 3, 4],
+                                  vec![1, 0, 2, 2],
+                                  vec![1.; 4]);
+        let mat_: CsMatI<f32, usize> = mat.to_other_types();
+        assert_eq!(mat_.indptr(), &[0, 1, 3, 4]);
+        assert_eq!(mat_.data(), &[1.0f32, 1., 1., 1.]);
+    }
+
+    #[test]
+    fn iter() {
+        let mat = CsMat::new_csc((3, 3),
+                                 vec![0, 1, 3, 4],
+                                 vec![1, 0, 2, 2],
+                                 vec![1.; 4]);
+        let mut iter = mat.iter();
+        assert_eq!(iter.next(), Some((&1., (1, 0))));
+        assert_eq!(iter.next(), Some((&1., (0, 1))));
+        assert_eq!(iter.next(), Some((&1., (2, 1))));
+        assert_eq!(iter.next(), Some((&1., (2, 2))));
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn from_triplets() {
+        // | 0 2 0 |
+        // | 1 0 0 |
+        // | 0 3 4 |
+        // the (2, 1) location is given twice and should be summed, and the
+        // triplets are not sorted
+        let rows = [2, 0, 1, 2, 2];
+        let cols = [1, 1, 0, 2, 1];
+        let data = [1., 2., 1., 4., 2.];
+        let csr = CsMat::from_triplets((3, 3), CSR, &rows, &cols, &data);
+        let expected = CsMat::new((3, 3),
+                                  vec![0, 1, 2, 4],
+                                  vec![1, 0, 1, 2],
+                                  vec![2., 1., 3., 4.]);
+        assert_eq!(csr, Ok(expected.to_owned()));
+
+        let csc = CsMat::from_triplets((3, 3), CSC, &rows, &cols, &data);
+        assert_eq!(csc, Ok(expected.to_csc()));
+    }
+
+    #[test]
+    fn from_triplets_out_of_bounds() {
+        let res = CsMat::from_triplets((3, 2), CSR, &[0, 1], &[1, 2], &[1, 1]);
+        assert_eq!(res, Err(SprsError::OutOfBoundsIndex));
+        let res = CsMat::from_triplets((3, 2), CSC, &[3, 1], &[1, 0], &[1, 1]);
+        assert_eq!(res, Err(SprsError::OutOfBoundsIndex));
+    }
+
+    #[test]
+    fn transpose_view() {
+        // | 0 2 0 |
+        // | 1 0 0 |
+        // | 0 3 4 |
+        let mat = CsMat::new((3, 3),
+                             vec![0, 1, 2, 4],
+                             vec![1, 0, 1, 2],
+                             vec![2., 1., 3., 4.]);
+        let mat_t = mat.transpose_view();
+        assert_eq!(mat_t.storage(), CSC);
+        assert_eq!(mat_t.indptr(), mat.indptr());
+        assert_eq!(mat_t.get(1, 0), Some(&2.));
+        assert_eq!(mat_t.get(0, 1), Some(&1.));
+        assert_eq!(mat_t.get(1, 2), Some(&3.));
+        assert_eq!(mat_t.get(2, 1), None);
+
+        let mat_tt = mat_t.transpose_view();
+        assert_eq!(mat_tt, mat.view());
+
+        let rect = CsMat::new((2, 3),
+                              vec![0, 1, 2],
+                              vec![2, 0],
+                              vec![1., 2.]);
+        let rect_t = rect.transpose_view();
+        assert_eq!(rect_t.shape(), (3, 2));
+        assert_eq!(rect_t.get(2, 0), Some(&1.));
+        assert_eq!(rect_t.transpose_view(), rect.view());
+    }
+
+    #[test]
+    fn transpose_same_storage() {
+        // | 0 2 0 0 |
+        // | 0 0 0 0 |
+        // | 0 3 4 0 |
+        let mat = CsMat::new((3, 4),
+                             vec![0, 1, 1, 3],
+                             vec![1, 1, 2],
+                             vec![2., 3., 4.]);
+        let mat_t = mat.transpose_same_storage();
+        assert_eq!(mat_t.storage(), CSR);
+        assert_eq!(mat_t.shape(), (4, 3));
+        assert_eq!(mat_t.indptr(), &[0, 0, 2, 3, 3]);
+        assert_eq!(mat_t.indices(), &[0, 2, 2]);
+        assert_eq!(mat_t.data(), &[2., 3., 4.]);
+        for i in 0..3 {
+            for j in 0..4 {
+                assert_eq!(mat_t.get(j, i), mat.get(i, j));
+            }
+        }
+
+        let mat_csc = mat.to_csc();
+        let mat_csc_t = mat_csc.transpose_same_storage();
+        assert_eq!(mat_csc_t.storage(), CSC);
+        assert_eq!(mat_csc_t, mat_t.to_csc());
+    }
+
+    #[test]
+    fn csr_csc_roundtrip() {
+        let a = mat1();
+        let a_csc = a.to_csc();
+        assert_eq!(a_csc, mat1_csc());
+        let a_csr = a_csc.to_csr();
+        assert_eq!(a_csr, a);
+        for (&val, (i, j)) in a.iter() {
+            assert_eq!(a_csc.get(i, j), Some(&val));
+        }
+        assert_eq!(a.to_csr(), a);
+        assert_eq!(a_csc.to_csc(), a_csc);
+    }
+
+    #[test]
+    fn to_csc_non_square() {
+        // | 1 0 0 2 0 |
+        // | 0 0 3 0 0 |
+        let a = CsMat::new((2, 5),
+                           vec![0, 2, 3],
+                           vec![0, 3, 2],
+                           vec![1., 2., 3.]);
+        let a_csc = a.to_csc();
+        assert_eq!(a_csc.shape(), (2, 5));
+        assert_eq!(a_csc.indptr(), &[0, 1, 1, 2, 3, 3]);
+        assert_eq!(a_csc.indices(), &[0, 1, 0]);
+        assert_eq!(a_csc.data(), &[1., 3., 2.]);
+        assert_eq!(a_csc.to_csr(), a);
+    }
+
+    #[test]
+    fn mul_dense_vec() {
+        // | 1 0 2 |
+        // | 0 0 0 |
+        // | 0 3 4 |
+        let mat = CsMat::new((3, 3),
+                             vec![0, 2, 2, 4],
+                             vec![0, 2, 1, 2],
+                             vec![1., 2., 3., 4.]);
+        let x = [1., 2., 3.];
+        assert_eq!(mat.mul_dense_vec(&x), vec![7., 0., 18.]);
+        assert_eq!(mat.to_csc().mul_dense_vec(&x), vec![7., 0., 18.]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn mul_dense_vec_dim_mismatch() {
+        let mat: CsMat<f64> = CsMat::eye(3);
+        mat.mul_dense_vec(&[1., 2.]);
+    }
+
+    #[test]
+    fn scale_keeps_structure() {
+        let a = mat1();
+        let mut b = a.to_owned();
+        b.scale(0.);
+        assert_eq!(b.indptr(), a.indptr());
+        assert_eq!(b.indices(), a.indices());
+        assert_eq!(b.nnz(), a.nnz());
+        assert!(b.data().iter().all(|&x| x == 0.));
+
+        let c = &a * 0.;
+        assert_eq!(c, b);
+
+        let d = &a * 2.;
+        assert_eq!(d, mat1_times_2());
+    }
+
+    #[test]
+    fn iter_order() {
+        // | 1 0 2 |
+        // | 0 0 0 |
+        // | 0 3 4 |
+        let mat = CsMat::new((3, 3),
+                             vec![0, 2, 2, 4],
+                             vec![0, 2, 1, 2],
+                             vec![1., 2., 3., 4.]);
+        let triplets: Vec<_> = mat.iter()
+                                  .map(|(&v, (i, j))| (i, j, v))
+                                  .collect();
+        assert_eq!(triplets,
+                   vec![(0, 0, 1.), (0, 2, 2.), (2, 1, 3.), (2, 2, 4.)]);
+
+        let mat_csc = mat.to_csc();
+        let triplets: Vec<_> = mat_csc.iter()
+                                      .map(|(&v, (i, j))| (i, j, v))
+                                      .collect();
+        assert_eq!(triplets,
+                   vec![(0, 0, 1.), (2, 1, 3.), (0, 2, 2.), (2, 2, 4.)]);
+    }
+
+    #[test]
+    fn density() {
+        let mat: CsMat<f64> = CsMat::eye(4);
+        assert_eq!(mat.nnz(), 4);
+        assert_eq!(mat.density(), 0.25);
+
+        let mat: CsMat<f64> = CsMat::zero((3, 2));
+        assert_eq!(mat.density(), 0.);
+
+        let mat: CsMat<f64> = CsMat::zero((0, 0));
+        assert_eq!(mat.density(), 0.);
+        let mat: CsMat<f64> = CsMat::zero((0, 5));
+        assert_eq!(mat.density(), 0.);
+
+        // the number of elements does not fit in an usize
+        let mat = CsMat::new_csc((usize::max_value(), 3),
+                                 vec![0, 1, 1, 1], vec![0], vec![1.]);
+        assert!(mat.density() > 0.);
+    }
+
+    #[test]
+    fn diag() {
+        // | 1 0 2 0 |
+        // | 0 0 0 0 |
+        // | 0 3 4 0 |
+        let mat = CsMat::new((3, 4),
+                             vec![0, 2, 2, 4],
+                             vec![0, 2, 1, 2],
+                             vec![1., 2., 3., 4.]);
+        let diag = mat.diag();
+        assert_eq!(diag.dim(), 3);
+        assert_eq!(diag.indices(), &[0, 2]);
+        assert_eq!(diag.data(), &[1., 4.]);
+        assert_eq!(diag.get(1), None);
+        assert_eq!(mat.to_csc().diag(), diag);
+        assert_eq!(mat.transpose_view().diag(), diag);
+    }
 
-impl<'a, 'b, N, I, IpS1, IS1, DS1, IpS2, IS2, DS2>
-Mul<&'b CsMatBase<N, I, IpS2, IS2, DS2>>
-for &'a CsMatBase<N, I, IpS1, IS1, DS1>
-where N: 'a + Copy + Num + Default,
-      I: 'a + SpIndex,
-      IpS1: 'a + Deref<Target=[I]>,
-      IS1: 'a + Deref<Target=[I]>,
-      DS1: 'a + Deref<Target=[N]>,
-      IpS2: 'b + Deref<Target=[I]>,
-      IS2: 'b + Deref<Target=[I]>,
-      DS2: 'b + Deref<Target=[N]> {
-    type Output = CsMatI<N, I>;
+    #[test]
+    fn outer_view() {
+        // | 1 0 2 |
+        // | 0 5 6 |
+        // | 0 3 4 |
+        let mat = CsMat::new((3, 3),
+                             vec![0, 2, 4, 6],
+                             vec![0, 2, 1, 2, 1, 2],
+                             vec![1., 2., 5., 6., 3., 4.]);
+        let row = mat.outer_view(1).unwrap();
+        assert_eq!(row.dim(), 3);
+        assert_eq!(row.indices(), &mat.indices()[2..4]);
+        assert_eq!(row.data(), &[5., 6.]);
+        assert!(mat.outer_view(3).is_none());
+
+        let mat_csc = mat.to_csc();
+        let col = mat_csc.outer_view(1).unwrap();
+        assert_eq!(col.indices(), &[1, 2]);
+        assert_eq!(col.data(), &[5., 3.]);
+    }
 
-    fn mul(self, rhs: &'b CsMatBase<N, I, IpS2, IS2, DS2>) -> CsMatI<N, I> {
-        match (self.storage(), rhs.storage()) {
-            (CSR, CSR) => {
-                let mut workspace = prod::workspace_csr(self, rhs);
-                prod::csr_mul_csr(self, rhs, &mut workspace)
-            }
-            (CSR, CSC) => {
-                let mut workspace = prod::workspace_csr(self, rhs);
-                prod::csr_mul_csr(self,
-                                  &rhs.to_other_storage(),
-                                  &mut workspace)
-            }
-            (CSC, CSR) => {
-                let mut workspace = prod::workspace_csc(self, rhs);
-                prod::csc_mul_csc(self, &rhs.to_other_storage(),
-                                  &mut workspace)
-            }
-            (CSC, CSC) => {
-                let mut workspace = prod::workspace_csc(self, rhs);
-                prod::csc_mul_csc(self, rhs, &mut workspace)
-            }
-        }
+    #[test]
+    fn nnz_in_outer() {
+        let mat = CsMat::new((3, 3),
+                             vec![0, 2, 2, 3],
+                             vec![0, 2, 1],
+                             vec![1., 2., 3.]);
+        assert_eq!(mat.nnz_in_outer(0), 2);
+        assert_eq!(mat.nnz_in_outer(1), 0);
+        assert_eq!(mat.nnz_in_outer(2), 1);
+        let mat_csc = mat.to_csc();
+        assert_eq!(mat_csc.nnz_in_outer(0), 1);
+        assert_eq!(mat_csc.nnz_in_outer(1), 1);
+        assert_eq!(mat_csc.nnz_in_outer(2), 1);
     }
-}
 
-impl<'a, 'b, N, I, IpS, IS, DS, DS2>
-Add<&'b ArrayBase<DS2, Ix2>>
-for &'a CsMatBase<N, I, IpS, IS, DS>
-where N: 'a + Copy + Num + Default,
-      I: 'a + SpIndex,
-      IpS: 'a + Deref<Target=[I]>,
-      IS: 'a + Deref<Target=[I]>,
-      DS: 'a + Deref<Target=[N]>,
-      DS2: 'b + ndarray::Data<Elem=N> {
-    type Output = Array<N, Ix2>;
+    #[test]
+    #[should_panic]
+    fn nnz_in_outer_out_of_bounds() {
+        let mat: CsMat<f64> = CsMat::eye(3);
+        mat.nnz_in_outer(3);
+    }
 
-    fn add(self, rhs: &'b ArrayBase<DS2, Ix2>) -> Array<N, Ix2> {
-        match (self.storage(), rhs.is_standard_layout()) {
-            (CSR, true) => {
-                    binop::add_dense_mat_same_ordering(self,
-                                                       rhs,
-                                                       N::one(),
-                                                       N::one()
-                                                      )
-                }
-                (CSR, false) => {
-                    let lhs = self.to_other_storage();
-                    binop::add_dense_mat_same_ordering(&lhs,
-                                                       rhs,
-                                                       N::one(),
-                                                       N::one()
-                                                      )
-                }
-                (CSC, true) => {
-                    let lhs = self.to_other_storage();
-                    binop::add_dense_mat_same_ordering(&lhs,
-                                                       rhs,
-                                                       N::one(),
-                                                       N::one()
-                                                      )
-                }
-                (CSC, false) => {
-                    binop::add_dense_mat_same_ordering(self,
-                                                       rhs,
-                                                       N::one(),
-                                                       N::one()
-                                                      )
-                }
-        }
+    #[test]
+    fn debug_fmt() {
+        let mat = CsMat::new((2, 2), vec![0, 1, 2], vec![1, 0], vec![1., 2.]);
+        assert_eq!(format!("{:?}", mat),
+                   "CsMatBase { storage: CSR, nrows: 2, ncols: 2, nnz: 2, \
+                    entries: [(0, 1): 1.0, (1, 0): 2.0] }");
+
+        let mat: CsMat<f64> = CsMat::eye(1000);
+        let fmt = format!("{:?}", mat);
+        assert!(fmt.len() < 250);
+        assert!(fmt.ends_with("(9, 9): 1.0, ...] }"));
     }
-}
 
-impl<'a, 'b, N, I, IpS, IS, DS, DS2>
-Mul<&'b ArrayBase<DS2, Ix2>>
-for &'a CsMatBase<N, I, IpS, IS, DS>
-where N: 'a + Copy + Num + Default,
-      I: 'a + SpIndex,
-      IpS: 'a + Deref<Target=[I]>,
-      IS: 'a + Deref<Target=[I]>,
-      DS: 'a + Deref<Target=[N]>,
-      DS2: 'b + ndarray::Data<Elem=N> {
-    type Output = Array<N, Ix2>;
+    #[test]
+    fn logical_eq() {
+        let a = mat1();
+        let a_csc = a.to_csc();
+        assert!(a.logical_eq(&a_csc));
+        assert!(a_csc.logical_eq(&a));
+        assert!(a.logical_eq(&a.view()));
+
+        // explicit zeros do not matter
+        let b = CsMat::new((2, 2), vec![0, 1, 2], vec![0, 1], vec![1., 2.]);
+        let c = CsMat::new_csc((2, 2),
+                               vec![0, 2, 3],
+                               vec![0, 1, 1],
+                               vec![1., 0., 2.]);
+        assert!(b.logical_eq(&c));
+        assert!(b != c.to_csr());
+
+        let d = CsMat::new((2, 2), vec![0, 1, 2], vec![0, 1], vec![1., 3.]);
+        assert!(!b.logical_eq(&d));
+        let e: CsMat<f64> = CsMat::zero((2, 3));
+        assert!(!e.logical_eq(&b));
+    }
 
-    fn mul(self, rhs: &'b ArrayBase<DS2, Ix2>) -> Array<N, Ix2> {
-        let rows = self.rows();
-        let cols = rhs.shape()[1];
-        // when the number of colums is small, it is more efficient
-        // to perform the product by iterating over the columns of
-        // the rhs, otherwise iterating by rows can take advantage of
-        // vectorized axpy.
-        match (self.storage(), cols >= 8) {
-            (CSR, true) => {
-                let mut res = Array::zeros((rows, cols));
-                prod::csr_mulacc_dense_rowmaj(self.view(),
-                                              rhs.view(),
-                                              res.view_mut()
-                                             );
-                res
-            }
-            (CSR, false) => {
-                let mut res = Array::zeros((rows, cols).f());
-                prod::csr_mulacc_dense_colmaj(self.view(),
-                                              rhs.view(),
-                                              res.view_mut()
-                                             );
-                res
-            }
-            (CSC, true) => {
-                let mut res = Array::zeros((rows, cols));
-                prod::csc_mulacc_dense_rowmaj(self.view(),
-                                              rhs.view(),
-                                              res.view_mut()
-                                             );
-                res
-            }
-            (CSC, false) => {
-                let mut res = Array::zeros((rows, cols).f());
-                prod::csc_mulacc_dense_colmaj(self.view(),
-                                              rhs.view(),
-                                              res.view_mut()
-                                             );
-                res
+    #[test]
+    fn approx_eq() {
+        let a = mat1();
+        let tol = 1e-6;
+        let below = a.map(|&x| x + 0.9e-6);
+        let above = a.map(|&x| x + 1.1e-6);
+        assert!(a.approx_eq(&below, tol));
+        assert!(!a.approx_eq(&above, tol));
+        assert!(a.approx_eq(&below.to_csc(), tol));
+        assert!(!a.to_csc().approx_eq(&above, tol));
+
+        // missing entries are compared to zero
+        let b = CsMat::new((2, 2), vec![0, 1, 2], vec![0, 1], vec![1., 2.]);
+        let c = CsMat::new((2, 2), vec![0, 2, 3], vec![0, 1, 1],
+                           vec![1., 0.9e-6, 2.]);
+        let d = CsMat::new((2, 2), vec![0, 2, 3], vec![0, 1, 1],
+                           vec![1., 1.1e-6, 2.]);
+        assert!(b.approx_eq(&c, tol));
+        assert!(c.approx_eq(&b, tol));
+        assert!(!b.approx_eq(&d, tol));
+        assert!(!d.approx_eq(&b, tol));
+
+        let e: CsMat<f64> = CsMat::zero((2, 3));
+        assert!(!e.approx_eq(&b, tol));
+    }
+
+    #[test]
+    fn eye() {
+        let eye: CsMat<f64> = CsMat::eye(3);
+        assert_eq!(eye.indptr(), &[0, 1, 2, 3]);
+        assert_eq!(eye.indices(), &[0, 1, 2]);
+        for i in 0..3 {
+            for j in 0..3 {
+                if i == j {
+                    assert_eq!(eye.get(i, j), Some(&1.));
+                } else {
+                    assert_eq!(eye.get(i, j), None);
+                }
             }
         }
+
+        let eye: CsMat<f64> = CsMat::eye(0);
+        assert_eq!(eye.shape(), (0, 0));
+        assert_eq!(eye.nnz(), 0);
+        assert_eq!(eye.indptr(), &[0]);
+        assert!(eye.check_compressed_structure().is_ok());
     }
-}
 
-impl<'a, 'b, N, I, IpS, IS, DS, DS2>
-Mul<&'b ArrayBase<DS2, Ix1>>
-for &'a CsMatBase<N, I, IpS, IS, DS>
-where N: 'a + Copy + Num + Default,
-      I: 'a + SpIndex,
-      IpS: 'a + Deref<Target=[I]>,
-      IS: 'a + Deref<Target=[I]>,
-      DS: 'a + Deref<Target=[N]>,
-      DS2: 'b + ndarray::Data<Elem=N> {
-    type Output = Array<N, Ix1>;
+    #[test]
+    fn eye_primitive_types() {
+        // the unit value is provided by num_traits::One, through the Num
+        // bound, for all primitive numeric types
+        let eye = CsMat::<f64>::eye(2);
+        assert_eq!(eye.to_dense(), arr2(&[[1., 0.], [0., 1.]]));
+        assert_eq!(CsMat::<f32>::eye(2).data(), &[1., 1.]);
+        assert_eq!(CsMat::<i32>::eye(2).data(), &[1, 1]);
+        assert_eq!(CsMat::<u8>::eye_csc(2).data(), &[1, 1]);
+        assert_eq!(CsMat::<i64>::from_diag(&[1, 1]), CsMat::eye(2));
+    }
 
-    fn mul(self, rhs: &'b ArrayBase<DS2, Ix1>) -> Array<N, Ix1> {
-        let rows = self.rows();
-        let cols = rhs.shape()[0];
-        let rhs_reshape = rhs.view().into_shape((cols, 1)).unwrap();
-        let mut res = Array::zeros(rows);
-        {
-            let res_reshape = res.view_mut().into_shape((rows, 1)).unwrap();
-            match self.storage() {
-                CSR => {
-                    prod::csr_mulacc_dense_colmaj(self.view(),
-                                                  rhs_reshape,
-                                                  res_reshape
-                                                 );
-                }
-                CSC => {
-                    prod::csc_mulacc_dense_colmaj(self.view(),
-                                                  rhs_reshape,
-                                                  res_reshape
-                                                 );
+    #[test]
+    fn zero() {
+        let zero: CsMat<f64> = CsMat::zero((3, 4));
+        assert!(zero.is_csr());
+        assert_eq!(zero.indptr(), &[0; 4]);
+        let zero_csc: CsMat<f64> = CsMat::zero_csc((3, 4));
+        assert!(zero_csc.is_csc());
+        assert_eq!(zero_csc.indptr(), &[0; 5]);
+        for mat in &[zero, zero_csc] {
+            assert!(mat.check_compressed_structure().is_ok());
+            assert_eq!(mat.shape(), (3, 4));
+            assert_eq!(mat.nnz(), 0);
+            for i in 0..3 {
+                for j in 0..4 {
+                    assert_eq!(mat.get(i, j), None);
                 }
             }
         }
-        res
     }
-}
 
-impl<N, I, IpS, IS, DS> Index<[usize; 2]> for CsMatBase<N, I, IpS, IS, DS>
-where I: SpIndex,
-      IpS: Deref<Target=[I]>,
-      IS: Deref<Target=[I]>,
-      DS: Deref<Target=[N]>
-{
-    type Output = N;
+    #[test]
+    fn outer_inner_iter() {
+        let mat = mat1();
+        assert_eq!(mat.outer_inner_iter().count(), mat.nnz());
+        assert!(mat.outer_inner_iter().eq(mat.iter()));
 
-    fn index(&self, index: [usize; 2]) -> &N {
-        let i = index[0];
-        let j = index[1];
-        self.get(i, j).unwrap()
+        // | 0 2 0 |
+        // | 1 0 0 |
+        // | 0 3 4 |
+        let mat = CsMat::new_csc((3, 3),
+                                 vec![0, 1, 3, 4],
+                                 vec![1, 0, 2, 2],
+                                 vec![1., 2., 3., 4.]);
+        let entries: Vec<_> = mat.outer_inner_iter().collect();
+        assert_eq!(entries, vec![(&1., (0, 1)),
+                                 (&2., (1, 0)),
+                                 (&3., (1, 2)),
+                                 (&4., (2, 2))]);
+        assert_eq!(entries.len(), mat.nnz());
     }
-}
 
-impl<N, I, IpS, IS, DS> IndexMut<[usize; 2]> for CsMatBase<N, I, IpS, IS, DS>
-where I: SpIndex,
-      IpS: Deref<Target=[I]>,
-      IS: Deref<Target=[I]>,
-      DS: DerefMut<Target=[N]>
-{
-    fn index_mut(&mut self, index: [usize; 2]) -> &mut N {
-        let i = index[0];
-        let j = index[1];
-        self.get_mut(i, j).unwrap()
+    #[test]
+    fn permute_rows_cols() {
+        use sparse::permutation::PermOwned;
+        // | 1 0 2 |
+        // | 0 0 3 |
+        // | 4 5 0 |
+        let mat = CsMat::new((3, 3),
+                             vec![0, 2, 3, 5],
+                             vec![0, 2, 2, 0, 1],
+                             vec![1., 2., 3., 4., 5.]);
+        let perm = PermOwned::new(vec![2, 0, 1]);
+        let dense = mat.to_dense();
+
+        let res = mat.permute_rows(perm.view());
+        let expected = arr2(&[[4., 5., 0.],
+                              [1., 0., 2.],
+                              [0., 0., 3.]]);
+        assert_eq!(res.to_dense(), expected);
+        assert_eq!(res.to_dense().row(0), dense.row(2));
+        assert_eq!(mat.to_csc().permute_rows(perm.view()).to_dense(),
+                   expected);
+
+        let res = mat.permute_cols(perm.view());
+        let expected = arr2(&[[2., 1., 0.],
+                              [3., 0., 0.],
+                              [0., 4., 5.]]);
+        assert_eq!(res.to_dense(), expected);
+        assert!(res.check_compressed_structure().is_ok());
+        let res_csc = mat.to_csc().permute_cols(perm.view());
+        assert_eq!(res_csc.to_dense(), expected);
+        assert!(res_csc.is_csc());
     }
-}
 
+    #[test]
+    fn mul_dense_vec_sym() {
+        // | 4 1 0 2 |
+        // | 1 3 0 0 |
+        // | 0 0 5 1 |
+        // | 2 0 1 6 |
+        let full = CsMat::new((4, 4),
+                              vec![0, 3, 5, 7, 10],
+                              vec![0, 1, 3, 0, 1, 2, 3, 0, 2, 3],
+                              vec![4., 1., 2., 1., 3., 5., 1., 2., 1., 6.]);
+        let lower = CsMat::new((4, 4),
+                               vec![0, 1, 3, 4, 7],
+                               vec![0, 0, 1, 2, 0, 2, 3],
+                               vec![4., 1., 3., 5., 2., 1., 6.]);
+        let x = [1., -2., 3., 0.5];
+        let expected = full.mul_dense_vec(&x);
+        assert_eq!(lower.mul_dense_vec_sym(&x), expected);
+        assert_eq!(lower.to_csc().mul_dense_vec_sym(&x), expected);
+        let upper = lower.transpose_view();
+        assert_eq!(upper.mul_dense_vec_sym(&x), expected);
+    }
 
-impl<N, I, IpS, IS, DS> Index<NnzIndex> for CsMatBase<N, I, IpS, IS, DS>
-where I: SpIndex,
-      IpS: Deref<Target=[I]>,
-      IS: Deref<Target=[I]>,
-      DS: Deref<Target=[N]>
-{
-    type Output = N;
+    #[test]
+    fn to_dense_csc() {
+        // | 1 0 2 |
+        // | 0 3 0 |
+        let mat = CsMat::new_csc((2, 3),
+                                 vec![0, 1, 2, 3],
+                                 vec![0, 1, 0],
+                                 vec![1., 3., 2.]);
+        let dense = mat.to_dense();
+        assert_eq!(dense, arr2(&[[1., 0., 2.],
+                                 [0., 3., 0.]]));
+        assert_eq!(dense.as_slice().unwrap(), &[1., 0., 2., 0., 3., 0.]);
+        assert_eq!(mat.to_csr().to_dense(), dense);
+    }
 
-    fn index(&self, index: NnzIndex) -> &N {
-        let NnzIndex(i) = index;
-        self.data().get(i).unwrap()
+    #[test]
+    fn append_outer_csvec() {
+        let mut mat = CsMat::empty(CSR, 3);
+        let rows: [(&[usize], &[f64]); 3] = [(&[0, 2], &[1., 2.]),
+                                              (&[], &[]),
+                                              (&[1], &[3.])];
+        for &(indices, data) in &rows {
+            let row = CsVecView::new_view(3, indices, data).unwrap();
+            mat = mat.append_outer_csvec(row);
+        }
+        let expected = CsMat::new((3, 3),
+                                  vec![0, 2, 2, 3],
+                                  vec![0, 2, 1],
+                                  vec![1., 2., 3.]);
+        assert_eq!(mat, expected);
+        assert!(mat.check_compressed_structure().is_ok());
+
+        let res = CsVecView::new_view(3, &[2, 0], &[1., 2.]);
+        assert_eq!(res, Err(SprsError::NonSortedIndices));
+        let res = CsVecView::new_view(3, &[0, 3], &[1., 2.]);
+        assert_eq!(res, Err(SprsError::OutOfBoundsIndex));
     }
-}
 
-impl<N, I, IpS, IS, DS> IndexMut<NnzIndex> for CsMatBase<N, I, IpS, IS, DS>
-where I: SpIndex,
-      IpS: Deref<Target=[I]>,
-      IS: Deref<Target=[I]>,
-      DS: DerefMut<Target=[N]>
-{
-    fn index_mut(&mut self, index: NnzIndex) -> &mut N {
-        let NnzIndex(i) = index;
-        self.data_mut().get_mut(i).unwrap()
+    #[test]
+    fn map_other_type() {
+        let mat = CsMat::new((2, 2), vec![0, 1, 2], vec![1, 0], vec![1.6, -3.]);
+        let doubled = mat.map(|&x| x * 2.);
+        assert_eq!(doubled.data(), &[3.2, -6.]);
+        let mat_int: CsMat<i32> = mat.map(|&x| x as i32);
+        assert_eq!(mat_int.indptr(), mat.indptr());
+        assert_eq!(mat_int.indices(), mat.indices());
+        assert_eq!(mat_int.data(), &[1, -3]);
+        let zeros = mat.map(|_| 0u8);
+        assert_eq!(zeros.nnz(), 2);
     }
-}
 
-impl<N, I, IpS, IS, DS> SparseMat for CsMatBase<N, I, IpS, IS, DS>
-where I: SpIndex,
-      IpS: Deref<Target=[I]>,
-      IS: Deref<Target=[I]>,
-      DS: Deref<Target=[N]>,
-{
-    fn rows(&self) -> usize {
-        self.rows()
+    #[test]
+    fn view_to_owned() {
+        let indptr: &[usize] = &[0, 2, 2, 3];
+        let indices: &[usize] = &[0, 2, 1];
+        let data: &[f64] = &[1., 2., 3.];
+        let owned = {
+            let view = CsMatView::new_view(CSC, (3, 3),
+                                           indptr, indices, data).unwrap();
+            let owned = view.to_owned();
+            assert_eq!(owned.view(), view);
+            owned
+        };
+        assert_eq!(owned.storage(), CSC);
+        assert_eq!(owned.shape(), (3, 3));
+        assert_eq!(owned.nnz(), 3);
+        assert_eq!(owned.indptr(), indptr);
+        assert_eq!(owned.indices(), indices);
+        assert_eq!(owned.data(), data);
+    }
+
+    #[test]
+    fn non_clone_values() {
+        // this only needs to compile: accessing the structure or values
+        // of a matrix should not require its elements to be Clone
+        #[derive(Debug, PartialEq)]
+        struct NonClone(i32);
+
+        let data = [NonClone(1), NonClone(2), NonClone(3)];
+        let mat = CsMatView::new_view(CSR, (3, 3), &[0, 2, 2, 3],
+                                      &[0, 2, 1], &data).unwrap();
+        assert_eq!(mat.rows(), 3);
+        assert_eq!(mat.cols(), 3);
+        assert_eq!(mat.nnz(), 3);
+        assert_eq!(mat.storage(), CSR);
+        assert_eq!(mat.nnz_in_outer(0), 2);
+        assert_eq!(mat.get(0, 2), Some(&NonClone(2)));
+        assert_eq!(mat[(2, 1)], NonClone(3));
+        assert_eq!(mat.iter().count(), 3);
+        assert_eq!(mat.outer_inner_iter().count(), 3);
+        assert_eq!(mat.outer_iterator().count(), 3);
+        assert_eq!(mat.outer_view(2).unwrap().data(), &[NonClone(3)]);
+        assert_eq!(mat.transpose_view().get(2, 0), Some(&NonClone(2)));
+        let mapped = mat.map(|x| x.0);
+        assert_eq!(mapped.data(), &[1, 2, 3]);
     }
 
-    fn cols(&self) -> usize {
-        self.cols()
+    #[test]
+    fn slice_outer() {
+        // | 1 0 0 2 |
+        // | 0 3 0 0 |
+        // | 0 0 0 0 |
+        // | 4 0 5 6 |
+        let mat = CsMat::new((4, 4),
+                             vec![0, 2, 3, 3, 6],
+                             vec![0, 3, 1, 0, 2, 3],
+                             vec![1., 2., 3., 4., 5., 6.]);
+        let sliced = mat.slice_outer(1..3);
+        assert_eq!(sliced.shape(), (2, 4));
+        assert_eq!(sliced.indptr(), &[0, 1, 1]);
+        assert_eq!(sliced.indices(), &[1]);
+        assert_eq!(sliced.data(), &[3.]);
+        assert!(sliced.check_compressed_structure().is_ok());
+
+        let sliced = mat.slice_outer(2..4);
+        assert_eq!(sliced.indptr(), &[0, 0, 3]);
+        assert_eq!(sliced.get(1, 2), Some(&5.));
+
+        let sliced = mat.to_csc().slice_outer(0..2);
+        assert_eq!(sliced.shape(), (4, 2));
+        assert_eq!(sliced.to_dense(), arr2(&[[1., 0.],
+                                             [0., 3.],
+                                             [0., 0.],
+                                             [4., 0.]]));
+
+        assert_eq!(mat.slice_outer(2..2).shape(), (0, 4));
     }
 
-    fn nnz(&self) -> usize {
-        self.nnz()
+    #[test]
+    fn neg() {
+        let mat = mat1();
+        let neg = -mat.to_owned();
+        assert_eq!(neg.indptr(), mat.indptr());
+        assert_eq!(neg.indices(), mat.indices());
+        for (&val, (i, j)) in mat.iter() {
+            assert_eq!(neg.get(i, j), Some(&-val));
+        }
     }
-}
 
-impl<'a, N, I, IpS, IS, DS> SparseMat for &'a CsMatBase<N, I, IpS, IS, DS>
-where I: 'a + SpIndex,
-      N: 'a,
-      IpS: Deref<Target=[I]>,
-      IS: Deref<Target=[I]>,
-      DS: Deref<Target=[N]>,
-{
-    fn rows(&self) -> usize {
-        (*self).rows()
+    #[test]
+    fn raw_accessors() {
+        let indptr = vec![0, 2, 2, 3];
+        let indices = vec![0, 2, 1];
+        let data = vec![1., 2., 3.];
+        let mat = CsMat::new((3, 3), indptr.clone(), indices.clone(),
+                             data.clone());
+        assert_eq!(mat.indptr(), &indptr[..]);
+        assert_eq!(mat.indices(), &indices[..]);
+        assert_eq!(mat.data(), &data[..]);
+        let mat = CsMat::new_csc((3, 3), indptr.clone(), indices.clone(),
+                                 data.clone());
+        assert_eq!(mat.indptr(), &indptr[..]);
+        assert_eq!(mat.indices(), &indices[..]);
+        assert_eq!(mat.data(), &data[..]);
     }
 
-    fn cols(&self) -> usize {
-        (*self).cols()
+    #[test]
+    fn outer_inner_sums() {
+        // | 1 0 2 |
+        // | 0 0 0 |
+        // | 3 4 5 |
+        let mat = CsMat::new((3, 3),
+                             vec![0, 2, 2, 5],
+                             vec![0, 2, 0, 1, 2],
+                             vec![1., 2., 3., 4., 5.]);
+        assert_eq!(mat.outer_sums(), vec![3., 0., 12.]);
+        assert_eq!(mat.inner_sums(), vec![4., 4., 7.]);
+        let mat_csc = mat.to_csc();
+        assert_eq!(mat_csc.outer_sums(), vec![4., 4., 7.]);
+        assert_eq!(mat_csc.inner_sums(), vec![3., 0., 12.]);
     }
 
-    fn nnz(&self) -> usize {
-        (*self).nnz()
+    #[test]
+    fn max_min_entry() {
+        // | 0 -2  0 |
+        // | 3  0 -7 |
+        // | 0  5  0 |
+        let mat = CsMat::new((3, 3),
+                             vec![0, 1, 3, 4],
+                             vec![1, 0, 2, 1],
+                             vec![-2., 3., -7., 5.]);
+        assert_eq!(mat.max_entry(), Some((2, 1, 5.)));
+        assert_eq!(mat.min_entry(), Some((1, 2, -7.)));
+        let mat_csc = mat.to_csc();
+        assert_eq!(mat_csc.max_entry(), Some((2, 1, 5.)));
+        assert_eq!(mat_csc.min_entry(), Some((1, 2, -7.)));
+
+        // only stored entries are considered
+        let neg = CsMat::new((2, 2), vec![0, 1, 1], vec![0], vec![-1.]);
+        assert_eq!(neg.max_entry(), Some((0, 0, -1.)));
+
+        let empty: CsMat<f64> = CsMat::zero((3, 4));
+        assert_eq!(empty.max_entry(), None);
+        assert_eq!(empty.min_entry(), None);
     }
-}
 
-impl<'a, N, I, IpS, IS, DS> IntoIterator for &'a CsMatBase<N, I, IpS, IS, DS>
-where I: 'a + SpIndex,
-      N: 'a,
-      IpS: Deref<Target=[I]>,
-      IS: Deref<Target=[I]>,
-      DS: Deref<Target=[N]>,
-{
-    type Item = (&'a N, (I, I));
-    type IntoIter = CsIter<'a, N, I>;
-    fn into_iter(self) -> Self::IntoIter {
-        self.iter()
+    #[test]
+    fn into_raw_roundtrip() {
+        let (storage, nrows, ncols, indptr, indices, data) =
+            mat1_csc().into_raw();
+        assert_eq!(storage, CSC);
+        let rebuilt = CsMat::new_csc((nrows, ncols), indptr, indices, data);
+        assert_eq!(rebuilt, mat1_csc());
     }
-}
 
-impl<'a, N, I> IntoIterator for CsMatViewI<'a, N, I>
-where I: 'a + SpIndex,
-      N: 'a,
-{
-    type Item = (&'a N, (I, I));
-    type IntoIter = CsIter<'a, N, I>;
-    fn into_iter(self) -> Self::IntoIter {
-        self.iter_rbr()
+    #[test]
+    fn hadamard_partial_overlap() {
+        // | 1 2 0 |   | 0 4 0 |
+        // | 0 3 0 | . | 5 6 0 |
+        // | 0 0 7 |   | 0 0 0 |
+        let a = CsMat::new((3, 3),
+                           vec![0, 2, 3, 4],
+                           vec![0, 1, 1, 2],
+                           vec![1., 2., 3., 7.]);
+        let b = CsMat::new((3, 3),
+                           vec![0, 1, 3, 3],
+                           vec![1, 0, 1],
+                           vec![4., 5., 6.]);
+        let expected = CsMat::new((3, 3),
+                                  vec![0, 1, 2, 2],
+                                  vec![1, 1],
+                                  vec![8., 18.]);
+        assert_eq!(a.hadamard(&b).unwrap(), expected);
+        assert_eq!(b.hadamard(&a).unwrap(), expected);
+        assert!(a.hadamard(&b.to_csc()).is_none());
+        assert!(a.hadamard(&CsMat::eye(2)).is_none());
     }
-}
 
-/// An iterator over non-overlapping blocks of a matrix,
-/// along the least-varying dimension
-pub struct ChunkOuterBlocks<'a, N: 'a, I: 'a + SpIndex> {
-    mat: CsMatViewI<'a, N, I>,
-    dims_in_bloc: usize,
-    bloc_count: usize,
-}
+    #[test]
+    fn frobenius_norm() {
+        // | 1 0 2 |
+        // | 0 0 4 |
+        let mat = CsMat::new((2, 3),
+                             vec![0, 2, 3],
+                             vec![0, 2, 2],
+                             vec![1., 2., 4.]);
+        let expected = 21f64.sqrt();
+        assert_eq!(mat.frobenius_norm(), expected);
+        assert_eq!(mat.to_csc().frobenius_norm(), expected);
+        let empty: CsMat<f64> = CsMat::zero((2, 2));
+        assert_eq!(empty.frobenius_norm(), 0.);
+    }
 
-impl<'a, N: 'a, I: 'a + SpIndex> Iterator for ChunkOuterBlocks<'a, N, I> {
-    type Item = CsMatViewI<'a, N, I>;
-    fn next(&mut self) -> Option<<Self as Iterator>::Item> {
-        let cur_dim = self.dims_in_bloc * self.bloc_count;
-        let end_dim = self.dims_in_bloc + cur_dim;
-        let count = if self.dims_in_bloc == 0 {
-            return None;
-        }
-        else if end_dim > self.mat.outer_dims() {
-            let count = self.mat.outer_dims() - cur_dim;
-            self.dims_in_bloc = 0;
-            count
+    #[test]
+    fn prune() {
+        // | 1 0 0 |
+        // | 0 0 2 |
+        // | 3 0 4 |
+        // with explicit zeros at (0, 2), (1, 0) and (1, 1)
+        let mut mat = CsMat::new((3, 3),
+                                 vec![0, 2, 5, 7],
+                                 vec![0, 2, 0, 1, 2, 0, 2],
+                                 vec![1., 0., 0., 0., 2., 3., 4.]);
+        let expected = CsMat::new((3, 3),
+                                  vec![0, 1, 2, 4],
+                                  vec![0, 2, 0, 2],
+                                  vec![1., 2., 3., 4.]);
+        assert!(mat.logical_eq(&expected));
+        assert_eq!(mat.nnz(), 7);
+        mat.prune();
+        assert_eq!(mat.nnz(), 4);
+        assert_eq!(mat, expected);
+    }
+
+    #[test]
+    fn zero_primitive_types() {
+        // the zero value needed by eg prune and to_dense is provided by
+        // num_traits::Zero, which covers all primitive numeric types
+        use num_traits::Zero;
+        fn check<N>(vals: [N; 2])
+        where N: Zero + PartialEq + Copy + ::std::fmt::Debug
+        {
+            let mut mat = CsMat::new((2, 2),
+                                     vec![0, 2, 3],
+                                     vec![0, 1, 1],
+                                     vec![vals[0], N::zero(), vals[1]]);
+            assert_eq!(mat.to_dense(), arr2(&[[vals[0], N::zero()],
+                                              [N::zero(), vals[1]]]));
+            mat.prune();
+            assert_eq!(mat.nnz(), 2);
+            assert!(mat.data().iter().all(|x| !x.is_zero()));
         }
-        else {
-            self.dims_in_bloc
-        };
-        let view = self.mat.middle_outer_views(cur_dim, count);
-        self.bloc_count += 1;
-        Some(view)
+        check::<f32>([1., 2.]);
+        check::<f64>([1., 2.]);
+        check::<i32>([1, 2]);
+        check::<i64>([1, 2]);
     }
-}
 
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_roundtrip() {
+        use serde_json;
+        let mat = mat1();
+        let json = serde_json::to_string(&mat).unwrap();
+        let res: CsMat<f64> = serde_json::from_str(&json).unwrap();
+        assert_eq!(res, mat);
 
-#[cfg(test)]
-mod test {
-    use sparse::{CsMatView, CsMat, CsMatI};
-    use super::CompressedStorage::{CSC, CSR};
-    use errors::SprsError;
-    use test_data::{mat1, mat1_csc, mat1_times_2};
+        let bad = r#"{"storage":"CSR","nrows":2,"ncols":2,
+                      "indptr":[0,1,2],"indices":[0,3],"data":[1.0,2.0]}"#;
+        assert!(serde_json::from_str::<CsMat<f64>>(bad).is_err());
+    }
 
     #[test]
-    fn test_new_csr_success() {
-        let indptr_ok : &[usize] = &[0, 1, 2, 3];
-        let indices_ok : &[usize] = &[0, 1, 2];
-        let data_ok : &[f64] = &[1., 1., 1.];
-        let m = CsMatView::new_view(CSR, (3, 3), indptr_ok, indices_ok, data_ok);
-        assert!(m.is_ok());
+    fn kron() {
+        // | 1 2 |     | 0 1 |
+        // | 0 3 | (x) | 4 0 |
+        let a = CsMat::new((2, 2), vec![0, 2, 3], vec![0, 1, 1],
+                           vec![1., 2., 3.]);
+        let b = CsMat::new((2, 2), vec![0, 1, 2], vec![1, 0],
+                           vec![1., 4.]);
+        let expected = arr2(&[[0., 1., 0., 2.],
+                              [4., 0., 8., 0.],
+                              [0., 0., 0., 3.],
+                              [0., 0., 12., 0.]]);
+        let res = a.kron(&b);
+        assert_eq!(res.storage(), CSR);
+        assert_eq!(res.to_dense(), expected);
+        res.check_compressed_structure().unwrap();
+
+        let res_csc = a.to_csc().kron(&b);
+        assert_eq!(res_csc.storage(), CSC);
+        assert_eq!(res_csc.to_dense(), expected);
+        res_csc.check_compressed_structure().unwrap();
+
+        // non square operands
+        let c = CsMat::new((1, 3), vec![0, 2], vec![0, 2], vec![1., 2.]);
+        let res = c.kron(&a);
+        assert_eq!(res.shape(), (2, 6));
+        assert_eq!(res.to_dense(),
+                   arr2(&[[1., 2., 0., 0., 2., 4.],
+                          [0., 3., 0., 0., 0., 6.]]));
     }
 
     #[test]
-    #[should_panic]
-    fn test_new_csr_bad_indptr_length() {
-        let indptr_fail1 : &[usize] = &[0, 1, 2];
-        let indices_ok : &[usize] = &[0, 1, 2];
-        let data_ok : &[f64] = &[1., 1., 1.];
-        let res = CsMatView::new_view(CSR,
-                                      (3, 3),
-                                      indptr_fail1,
-                                      indices_ok,
-                                      data_ok);
-        res.unwrap(); // unreachable
+    fn trace() {
+        // | 1 0 2 |
+        // | 0 0 3 |
+        // | 4 0 5 |
+        let mat = CsMat::new((3, 3),
+                             vec![0, 2, 3, 5],
+                             vec![0, 2, 2, 0, 2],
+                             vec![1., 2., 3., 4., 5.]);
+        assert_eq!(mat.trace(), 6.);
+        assert_eq!(mat.to_csc().trace(), 6.);
+        let empty: CsMat<f64> = CsMat::zero((2, 2));
+        assert_eq!(empty.trace(), 0.);
     }
 
     #[test]
     #[should_panic]
-    fn test_new_csr_out_of_bounds_index() {
-        let indptr_ok : &[usize] = &[0, 1, 2, 3];
-        let data_ok : &[f64] = &[1., 1., 1.];
-        let indices_fail2 : &[usize] = &[0, 1, 4];
-        let res = CsMatView::new_view(CSR,
-                                      (3, 3),
-                                      indptr_ok,
-                                      indices_fail2,
-                                      data_ok);
-        res.unwrap(); //unreachable
+    fn trace_non_square() {
+        let mat: CsMat<f64> = CsMat::zero((2, 3));
+        mat.trace();
     }
 
     #[test]
-    #[should_panic]
-    fn test_new_csr_bad_nnz_count() {
-        let indices_ok : &[usize] = &[0, 1, 2];
-        let data_ok : &[f64] = &[1., 1., 1.];
-        let indptr_fail2 : &[usize] = &[0, 1, 2, 4];
-        let res = CsMatView::new_view(CSR,
-                                      (3, 3),
-                                      indptr_fail2,
-                                      indices_ok,
-                                      data_ok);
-        res.unwrap(); //unreachable
+    fn empty_outer_indices() {
+        // | 1 0 2 |
+        // | 0 0 0 |
+        // | 0 3 0 |
+        // | 0 0 0 |
+        let mat = CsMat::new((4, 3),
+                             vec![0, 2, 2, 3, 3],
+                             vec![0, 2, 1],
+                             vec![1., 2., 3.]);
+        assert_eq!(mat.empty_outer_indices(), vec![1, 3]);
+        assert_eq!(mat.to_csc().empty_outer_indices(), Vec::<usize>::new());
+        assert_eq!(CsMat::<f64>::eye(3).empty_outer_indices(),
+                   Vec::<usize>::new());
     }
 
+    #[cfg(feature = "rayon")]
     #[test]
-    #[should_panic]
-    fn test_new_csr_data_indices_mismatch1() {
-        let indptr_ok : &[usize] = &[0, 1, 2, 3];
-        let data_ok : &[f64] = &[1., 1., 1.];
-        let indices_fail1 : &[usize] = &[0, 1];
-        let res = CsMatView::new_view(CSR,
-                                      (3, 3),
-                                      indptr_ok,
-                                      indices_fail1,
-                                      data_ok);
-        res.unwrap(); //unreachable
+    fn mul_dense_vec_par() {
+        let mat = mat1();
+        let x = [1., 2., 3., 4., 5.];
+        assert_eq!(mat.mul_dense_vec_par(&x), mat.mul_dense_vec(&x));
+        let mat_csc = mat1_csc();
+        assert_eq!(mat_csc.mul_dense_vec_par(&x), mat_csc.mul_dense_vec(&x));
     }
 
     #[test]
-    #[should_panic]
-    fn test_new_csr_data_indices_mismatch2() {
-        let indptr_ok : &[usize] = &[0, 1, 2, 3];
-        let indices_ok : &[usize] = &[0, 1, 2];
-        let data_fail1 : &[f64] = &[1., 1., 1., 1.];
-        let res = CsMatView::new_view(CSR,
-                                      (3, 3),
-                                      indptr_ok,
-                                      indices_ok,
-                                      data_fail1);
-        res.unwrap(); //unreachable
+    fn densest_outer() {
+        let mat = mat1();
+        assert_eq!(mat.densest_outer(), Some((0, 2)));
+        let mat_csc = mat1_csc();
+        assert_eq!(mat_csc.densest_outer(), Some((3, 3)));
+        let empty: CsMat<f64> = CsMat::zero((3, 3));
+        assert_eq!(empty.densest_outer(), None);
+        let no_dims: CsMat<f64> = CsMat::zero((0, 3));
+        assert_eq!(no_dims.densest_outer(), None);
     }
 
     #[test]
-    #[should_panic]
-    fn test_new_csr_data_indices_mismatch3() {
-        let indptr_ok : &[usize] = &[0, 1, 2, 3];
-        let indices_ok : &[usize] = &[0, 1, 2];
-        let data_fail2 : &[f64] = &[1., 1.,];
-        let res = CsMatView::new_view(CSR,
-                                      (3, 3),
-                                      indptr_ok,
-                                      indices_ok,
-                                      data_fail2);
-        res.unwrap(); //unreachable
+    fn from_diag() {
+        let mat = CsMat::from_diag(&[1., 2., 3.]);
+        assert_eq!(mat.get(1, 1), Some(&2.));
+        assert_eq!(mat.get(1, 0), None);
+        assert_eq!(mat.get(1, 2), None);
+        assert_eq!(mat.nnz(), 3);
+        assert_eq!(mat.diag().to_dense(), arr1(&[1., 2., 3.]));
+        let with_zero = CsMat::from_diag(&[1., 0.]);
+        assert_eq!(with_zero.nnz(), 2);
     }
 
     #[test]
-    fn test_new_csr_fails() {
-        let indices_ok : &[usize] = &[0, 1, 2];
-        let data_ok : &[f64] = &[1., 1., 1.];
-        let indptr_fail3 : &[usize] = &[0, 2, 1, 3];
-        assert_eq!(CsMatView::new_view(CSR,
-                                       (3, 3),
-                                       indptr_fail3,
-                                       indices_ok,
-                                       data_ok),
-                   Err(SprsError::UnsortedIndptr));
+    fn tril_triu() {
+        let mat = CsMat::new((3, 3),
+                             vec![0, 3, 5, 7],
+                             vec![0, 1, 2, 0, 1, 1, 2],
+                             vec![1., 2., 3., 4., 5., 6., 7.]);
+        assert_eq!(mat.tril(0).to_dense(), arr2(&[[1., 0., 0.],
+                                                  [4., 5., 0.],
+                                                  [0., 6., 7.]]));
+        assert_eq!(mat.tril(-1).to_dense(), arr2(&[[0., 0., 0.],
+                                                   [4., 0., 0.],
+                                                   [0., 6., 0.]]));
+        assert_eq!(mat.triu(0).to_dense(), arr2(&[[1., 2., 3.],
+                                                  [0., 5., 0.],
+                                                  [0., 0., 7.]]));
+        assert_eq!(mat.triu(1).to_dense(), arr2(&[[0., 2., 3.],
+                                                  [0., 0., 0.],
+                                                  [0., 0., 0.]]));
+        let mat_csc = mat.to_csc();
+        assert_eq!(mat_csc.tril(-1).to_dense(), mat.tril(-1).to_dense());
+        assert_eq!(mat_csc.triu(0).to_dense(), mat.triu(0).to_dense());
+        assert_eq!(mat.tril(-1).nnz(), 2);
     }
 
     #[test]
-    fn test_new_csr_fail_indices_ordering() {
-        let indptr: &[usize] = &[0, 2, 4, 5, 6, 7];
-        // good indices would be [2, 3, 3, 4, 2, 1, 3];
-        let indices: &[usize] = &[3, 2, 3, 4, 2, 1, 3];
-        let data: &[f64] = &[
-            0.35310881, 0.42380633, 0.28035896, 0.58082095,
-            0.53350123, 0.88132896, 0.72527863];
-        assert_eq!(CsMatView::new_view(CSR,
-                                       (5, 5),
-                                       indptr,
-                                       indices,
-                                       data),
-                   Err(SprsError::NonSortedIndices));
+    fn split_ldu() {
+        let mat = CsMat::new((3, 3),
+                             vec![0, 3, 5, 7],
+                             vec![0, 1, 2, 0, 1, 1, 2],
+                             vec![1., 2., 3., 4., 5., 6., 7.]);
+        for mat in &[mat.view(), mat.to_csc().view()] {
+            let (lower, diag, upper) = mat.split_ldu();
+            assert_eq!(lower, mat.tril(-1));
+            assert_eq!(diag, CsVecI::new(3, vec![0, 1, 2], vec![1., 5., 7.]));
+            assert_eq!(upper, mat.triu(1));
+            let diag_mat = CsMat::from_diag(&diag.to_dense().to_vec());
+            let sum = &(&lower + &diag_mat) + &upper;
+            assert_eq!(sum.to_dense(), mat.to_dense());
+        }
+
+        let (lower, diag, upper) = mat1().split_ldu();
+        assert_eq!(lower.nnz() + diag.nnz() + upper.nnz(), mat1().nnz());
+        assert_eq!(diag.nnz(), 1);
     }
 
     #[test]
-    fn test_new_csr_csc_success() {
-        let indptr_ok : &[usize] = &[0, 2, 5, 6];
-        let indices_ok : &[usize] = &[2, 3, 1, 2, 3, 3];
-        let data_ok : &[f64] = &[
-            0.05734571, 0.15543348, 0.75628258,
-            0.83054515, 0.71851547, 0.46202352];
-        assert!(CsMatView::new_view(CSR, (3, 4),
-                                    indptr_ok, indices_ok, data_ok).is_ok());
-        assert!(CsMatView::new_view(CSC, (4, 3),
-                                    indptr_ok, indices_ok, data_ok).is_ok());
+    fn inner_view() {
+        let mat = mat1();
+        let col = mat.inner_view(3);
+        assert_eq!(col, CsVecI::new(5, vec![0, 1, 4], vec![4., 2., 7.]));
+        assert_eq!(mat.inner_view(0), CsVecI::empty(5));
+        let mat_csc = mat1_csc();
+        assert_eq!(mat_csc.inner_view(0), mat.outer_view(0).unwrap()
+                                             .to_owned());
     }
 
     #[test]
-    #[should_panic]
-    fn test_new_csc_bad_indptr_length() {
-        let indptr_ok : &[usize] = &[0, 2, 5, 6];
-        let indices_ok : &[usize] = &[2, 3, 1, 2, 3, 3];
-        let data_ok : &[f64] = &[
-            0.05734571, 0.15543348, 0.75628258,
-            0.83054515, 0.71851547, 0.46202352];
-        let res = CsMatView::new_view(CSC,
-                                      (3, 4),
-                                      indptr_ok,
-                                      indices_ok,
-                                      data_ok);
-        res.unwrap(); //unreachable
+    fn same_sparsity() {
+        let mat = mat1();
+        let doubled = mat1_times_2();
+        assert!(mat.same_sparsity(&doubled));
+        assert!(mat.same_sparsity(&mat.map(|&x| x as i32)));
+        assert!(!mat.same_sparsity(&mat1_csc()));
+        assert!(!mat.same_sparsity(&CsMat::<f64>::eye(5)));
     }
 
+    #[test]
+    fn papt() {
+        use sparse::permutation::PermOwned;
+        // | 1 2 0 |
+        // | 0 3 4 |
+        // | 5 0 6 |
+        let mat = CsMat::new((3, 3),
+                             vec![0, 2, 4, 6],
+                             vec![0, 1, 1, 2, 0, 2],
+                             vec![1., 2., 3., 4., 5., 6.]);
+        let perm = PermOwned::new(vec![2, 0, 1]);
+        let dense = mat.to_dense();
+        let mut expected = dense.clone();
+        for i in 0..3 {
+            for j in 0..3 {
+                expected[[i, j]] = dense[[perm.at(i), perm.at(j)]];
+            }
+        }
+        let res = mat.papt(perm.view());
+        res.check_compressed_structure().unwrap();
+        assert_eq!(res.to_dense(), expected);
+        let res_csc = mat.to_csc().papt(perm.view());
+        res_csc.check_compressed_structure().unwrap();
+        assert_eq!(res_csc.to_dense(), expected);
+    }
 
     #[test]
-    fn test_new_csr_vec_borrowed() {
-        let indptr_ok = vec![0, 1, 2, 3];
-        let indices_ok = vec![0, 1, 2];
-        let data_ok : Vec<f64> = vec![1., 1., 1.];
-        assert!(CsMatView::new_view(CSR, (3, 3),
-                                    &indptr_ok, &indices_ok, &data_ok).is_ok());
+    fn display() {
+        let mat = CsMat::new((2, 2), vec![0, 1, 3], vec![0, 0, 1],
+                             vec![1, 23, 0]);
+        assert_eq!(format!("{}", mat), " 1  .\n23  .");
+        assert_eq!(format!("{}", mat.to_csc()), " 1  .\n23  .");
+        let big: CsMat<f64> = CsMat::eye(30);
+        assert_eq!(format!("{}", big), "30x30 CSR matrix with 30 non-zeros");
+    }
+
+    #[test]
+    fn outer_nnz_histogram() {
+        // row lengths: 2, 2, 1, 1, 1
+        assert_eq!(mat1().outer_nnz_histogram(), vec![0, 3, 2]);
+        // column lengths: 0, 1, 2, 3, 1
+        assert_eq!(mat1_csc().outer_nnz_histogram(), vec![1, 2, 1, 1]);
+        let empty: CsMat<f64> = CsMat::zero((3, 2));
+        assert_eq!(empty.outer_nnz_histogram(), vec![3]);
+        let no_dims: CsMat<f64> = CsMat::zero((0, 2));
+        assert_eq!(no_dims.outer_nnz_histogram(), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn u32_indices() {
+        // | 1 0 2 |
+        // | 0 3 0 |
+        let mat: CsMatI<f64, u32> = CsMatI::new((2, 3),
+                                                vec![0, 2, 3],
+                                                vec![0, 2, 1],
+                                                vec![1., 2., 3.]);
+        assert_eq!(mat.get(0, 2), Some(&2.));
+        assert_eq!(mat.get(1, 1), Some(&3.));
+        assert_eq!(mat.get(1, 2), None);
+        assert_eq!(mat[(0, 0)], 1.);
+        assert_eq!(mat.indptr(), &[0u32, 2, 3]);
+        let mat_csc = mat.to_csc();
+        assert_eq!(mat_csc.indices(), &[0u32, 1, 0]);
+        assert_eq!(mat_csc.to_dense(), mat.to_dense());
+    }
+
+    #[test]
+    fn normalize_outer() {
+        // | 1 3 0 |
+        // | 0 0 0 |
+        // | 0 2 0 |
+        let mut mat = CsMat::new((3, 3), vec![0, 2, 2, 3], vec![0, 1, 1],
+                                 vec![1., 3., 2.]);
+        mat.normalize_outer();
+        assert_eq!(mat.data(), &[0.25, 0.75, 1.]);
+        assert_eq!(mat.outer_sums(), vec![1., 0., 1.]);
+
+        let mut mat_csc = mat1_csc();
+        mat_csc.normalize_outer();
+        for (sum, vec) in mat_csc.outer_sums().iter()
+                                 .zip(mat_csc.outer_iterator()) {
+            if vec.nnz() > 0 {
+                assert!((sum - 1.).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn insert_empty_outer() {
+        // | 1 0 2 |
+        // | 0 0 0 |
+        // | 3 0 4 |
+        let mut mat = CsMat::new((3, 3), vec![0, 2, 2, 4], vec![0, 2, 0, 2],
+                                 vec![1., 2., 3., 4.]);
+        mat.insert(1, 2, 6.);
+        mat.insert(1, 0, 5.);
+        let expected = CsMat::new((3, 3),
+                                  vec![0, 2, 4, 6],
+                                  vec![0, 2, 0, 2, 0, 2],
+                                  vec![1., 2., 5., 6., 3., 4.]);
+        assert_eq!(mat, expected);
+        mat.check_compressed_structure().unwrap();
+
+        let mut mat_csc = CsMat::new_csc((2, 3), vec![0, 1, 1, 2],
+                                         vec![1, 0], vec![1., 2.]);
+        mat_csc.insert(1, 1, 3.);
+        mat_csc.insert(0, 1, 4.);
+        assert_eq!(mat_csc.indptr(), &[0, 1, 3, 4]);
+        assert_eq!(mat_csc.indices(), &[1, 0, 1, 0]);
+        assert_eq!(mat_csc.data(), &[1., 4., 3., 2.]);
+    }
+
+    #[test]
+    fn with_capacity() {
+        let expected = mat1();
+        for &(outer_hint, nnz_hint) in &[(0, 0), (5, 7), (2, 3), (10, 20)] {
+            let mut mat = CsMat::with_capacity(CSR, 5, outer_hint, nnz_hint);
+            assert!(mat.indices.capacity() >= nnz_hint);
+            for vec in expected.outer_iterator() {
+                mat = mat.append_outer_csvec(vec);
+            }
+            assert_eq!(mat, expected);
+        }
+    }
+
+    #[test]
+    fn into_iter_owned() {
+        let expected: Vec<_> = mat1().iter()
+                                     .map(|(&v, (i, j))| (i, j, v))
+                                     .collect();
+        let triplets: Vec<_> = mat1().into_iter().collect();
+        assert_eq!(triplets.len(), mat1().nnz());
+        assert_eq!(triplets, expected);
+
+        let triplets: Vec<_> = mat1_csc().into_iter().collect();
+        assert_eq!(triplets.len(), mat1().nnz());
+        assert_eq!(triplets[0], (3, 1, 8.));
+        assert_eq!(triplets[6], (1, 4, 5.));
+
+        let empty: CsMat<f64> = CsMat::zero((3, 3));
+        assert_eq!(empty.into_iter().next(), None);
+    }
+
+    #[test]
+    fn extend_outer() {
+        let mut mat = CsMat::new((1, 4), vec![0, 2], vec![0, 3], vec![1., 2.]);
+        let rows = vec![CsVecI::new(4, vec![1, 2], vec![3., 4.]),
+                        CsVecI::new(4, vec![0], vec![5.])];
+        mat.extend_outer(rows).unwrap();
+        let expected = CsMat::new((3, 4),
+                                  vec![0, 2, 4, 5],
+                                  vec![0, 3, 1, 2, 0],
+                                  vec![1., 2., 3., 4., 5.]);
+        assert_eq!(mat, expected);
+
+        let mut mat_csc = CsMat::new_csc((4, 1), vec![0, 1], vec![2], vec![1.]);
+        mat_csc.extend_outer(Some(CsVecI::new(4, vec![3], vec![2.])))
+               .unwrap();
+        assert_eq!(mat_csc.shape(), (4, 2));
+        assert_eq!(mat_csc.get(3, 1), Some(&2.));
     }
 
     #[test]
-    fn test_new_csr_vec_owned() {
-        let indptr_ok = vec![0, 1, 2, 3];
-        let indices_ok = vec![0, 1, 2];
-        let data_ok : Vec<f64> = vec![1., 1., 1.];
-        assert!(CsMat::new_(CSR, (3, 3),
-                                 indptr_ok, indices_ok, data_ok).is_ok());
+    fn extend_outer_fail() {
+        let mut mat = CsMat::new((1, 4), vec![0, 2], vec![0, 3], vec![1., 2.]);
+        let res = mat.extend_outer(Some(CsVecI::new(10, vec![0, 1],
+                                                    vec![3., 4.])));
+        assert_eq!(res, Err(SprsError::IncompatibleDimensions));
+        let out_of_bounds = CsVecI {
+            dim: 4,
+            indices: vec![4],
+            data: vec![3.],
+        };
+        let res = mat.extend_outer(Some(out_of_bounds));
+        assert_eq!(res, Err(SprsError::OutOfBoundsIndex));
+        let unsorted = CsVecI {
+            dim: 4,
+            indices: vec![2, 1],
+            data: vec![3., 4.],
+        };
+        let res = mat.extend_outer(Some(unsorted));
+        assert_eq!(res, Err(SprsError::NonSortedIndices));
+        assert_eq!(mat.shape(), (1, 4));
     }
 
     #[test]
-    fn owned_csr_unsorted_indices() {
-        let indptr = vec![0, 3, 3, 5, 6, 7];
-        let indices_sorted = &[1, 2, 3, 2, 3, 4, 4];
-        let indices_shuffled = vec![1, 3, 2, 2, 3, 4, 4];
-        let mut data: Vec<i32> = (0..7).collect();
-        let m = CsMat::new((5, 5), indptr, indices_shuffled, data.clone());
-        assert_eq!(m.indices(), indices_sorted);
-        data.swap(1, 2);
-        assert_eq!(m.data(), &data[..]);
+    fn gram() {
+        // | 1 0 2 |
+        // | 0 3 0 |
+        // | 4 0 5 |
+        // | 0 6 7 |
+        let a = CsMat::new((4, 3),
+                           vec![0, 2, 3, 5, 7],
+                           vec![0, 2, 1, 0, 2, 1, 2],
+                           vec![1., 2., 3., 4., 5., 6., 7.]);
+        let dense = a.to_dense();
+        let expected = dense.t().dot(&dense);
+        let gram = a.gram();
+        assert_eq!(gram.shape(), (3, 3));
+        assert_eq!(gram.storage(), CSR);
+        assert_eq!(gram.to_dense(), expected);
+        assert!(gram.is_symmetric());
+        assert!(gram.check_compressed_structure().is_ok());
+        assert_eq!(a.to_csc().gram(), gram);
+
+        let dense = mat1().to_dense();
+        let gram = mat1_csc().gram();
+        assert_eq!(gram.to_dense(), dense.t().dot(&dense));
+        assert!(gram.check_compressed_structure().is_ok());
+
+        let b = CsMat::new((2, 3), vec![0, 1, 2], vec![2, 0], vec![1., 2.]);
+        assert_eq!(b.gram().to_dense(), arr2(&[[4., 0., 0.],
+                                               [0., 0., 0.],
+                                               [0., 0., 1.]]));
     }
 
     #[test]
-    fn new_csr_with_empty_row() {
-        let indptr: &[usize] = &[0, 3, 3, 5, 6, 7];
-        let indices: &[usize] = &[1, 2, 3, 2, 3, 4, 4];
-        let data: &[f64] = &[
-            0.75672424, 0.1649078, 0.30140296, 0.10358244,
-            0.6283315, 0.39244208, 0.57202407];
-        assert!(CsMatView::new_view(CSR,
-                                    (5, 5),
-                                    indptr,
-                                    indices,
-                                    data).is_ok());
+    fn shrink_to_fit() {
+        let expected = mat1();
+        let mut mat = mat1();
+        mat.reserve_nnz(100);
+        assert!(mat.indices.capacity() >= 107);
+        assert!(mat.data.capacity() >= 107);
+        mat.shrink_to_fit();
+        assert!(mat.indices.capacity() < 107);
+        assert!(mat.data.capacity() < 107);
+        assert_eq!(mat, expected);
     }
 
     #[test]
-    fn csr_to_csc() {
-        let a = mat1();
-        let a_csc_ground_truth = mat1_csc();
-        let a_csc = a.to_other_storage();
-        assert_eq!(a_csc, a_csc_ground_truth);
+    fn outer_cursor() {
+        // | 0 1 0 2 0 3 |
+        let mat = CsMat::new((1, 6), vec![0, 3], vec![1, 3, 5],
+                             vec![1., 2., 3.]);
+        let mut cursor = mat.outer_cursor(0);
+        let res: Vec<_> = (0..6).map(|j| cursor.seek(j).cloned()).collect();
+        assert_eq!(res, vec![None, Some(1.), None, Some(2.), None, Some(3.)]);
+        assert_eq!(cursor.seek(5), Some(&3.));
+        assert_eq!(cursor.seek(7), None);
+        // seeking backwards is still correct
+        assert_eq!(cursor.seek(3), Some(&2.));
+        assert_eq!(cursor.seek(1), Some(&1.));
+
+        let mat = mat1_csc();
+        let mut cursor = mat.outer_cursor(3);
+        assert_eq!(cursor.seek(0), Some(&4.));
+        assert_eq!(cursor.seek(2), None);
+        assert_eq!(cursor.seek(4), Some(&7.));
     }
 
     #[test]
-    fn test_self_smul() {
-        let mut a = mat1();
-        a.scale(2.);
-        let c_true = mat1_times_2();
-        assert_eq!(a.indptr(), c_true.indptr());
-        assert_eq!(a.indices(), c_true.indices());
-        assert_eq!(a.data(), c_true.data());
+    fn inner_nnz_counts() {
+        assert_eq!(mat1().inner_nnz_counts(), vec![0, 1, 2, 3, 1]);
+        assert_eq!(mat1_csc().inner_nnz_counts(), vec![2, 2, 1, 1, 1]);
+        let empty: CsMat<f64> = CsMat::zero((2, 3));
+        assert_eq!(empty.inner_nnz_counts(), vec![0, 0, 0]);
     }
 
     #[test]
-    fn outer_block_iter() {
-        let mat : CsMat<f64> = CsMat::eye(11);
-        let mut block_iter = mat.outer_block_iter(3);
-        assert_eq!(block_iter.next().unwrap().rows(), 3);
-        assert_eq!(block_iter.next().unwrap().rows(), 3);
-        assert_eq!(block_iter.next().unwrap().rows(), 3);
-        assert_eq!(block_iter.next().unwrap().rows(), 2);
-        assert_eq!(block_iter.next(), None);
-
-        let mut block_iter = mat.outer_block_iter(4);
-        assert_eq!(block_iter.next().unwrap().cols(), 11);
-        block_iter.next().unwrap();
-        block_iter.next().unwrap();
-        assert_eq!(block_iter.next(), None);
+    fn outer_iterator_mut_scale() {
+        let mut mat = mat1();
+        for mut vec in mat.outer_iterator_mut() {
+            vec.scale(2.);
+        }
+        let mut expected = mat1();
+        expected.scale(2.);
+        assert_eq!(mat, expected);
+        assert_eq!(mat, mat1_times_2());
     }
 
     #[test]
-    fn nnz_index() {
-        let mat : CsMat<f64> = CsMat::eye(11);
+    fn to_triplets() {
+        let mat = mat1();
+        let (rows, cols, data) = mat.to_triplets();
+        assert_eq!(rows, vec![0, 0, 1, 1, 2, 3, 4]);
+        assert_eq!(cols, vec![2, 3, 3, 4, 2, 1, 3]);
+        assert_eq!(data, vec![3., 4., 2., 5., 5., 8., 7.]);
+        let res = CsMat::from_triplets(mat.shape(), CSR, &rows, &cols, &data);
+        assert_eq!(res.unwrap(), mat);
+
+        let mat = mat1_csc();
+        let (rows, cols, data) = mat.to_triplets();
+        let res = CsMat::from_triplets(mat.shape(), CSC, &rows, &cols, &data);
+        assert_eq!(res.unwrap(), mat);
+    }
 
-        assert_eq!(mat.nnz_index(2, 3), None);
-        assert_eq!(mat.nnz_index(5, 7), None);
-        assert_eq!(mat.nnz_index(0, 11), None);
-        assert_eq!(mat.nnz_index(0, 0), Some(super::NnzIndex(0)));
-        assert_eq!(mat.nnz_index(7, 7), Some(super::NnzIndex(7)));
-        assert_eq!(mat.nnz_index(10, 10), Some(super::NnzIndex(10)));
+    #[test]
+    fn abs() {
+        let mat = CsMat::new((2, 2), vec![0, 2, 3], vec![0, 1, 1],
+                             vec![-1., 2., -3.5]);
+        let expected = CsMat::new((2, 2), vec![0, 2, 3], vec![0, 1, 1],
+                                  vec![1., 2., 3.5]);
+        assert_eq!(mat.abs(), expected);
+        let mat_int = CsMat::new_csc((2, 2), vec![0, 1, 1], vec![1], vec![-4]);
+        assert_eq!(mat_int.abs().data(), &[4]);
+    }
 
-        let index = mat.nnz_index(8, 8).unwrap();
-        assert_eq!(mat[index], 1.);
-        let mut mat = mat;
-        mat[index] = 2.;
-        assert_eq!(mat[index], 2.);
+    #[test]
+    fn norm_inf_one() {
+        // | 1 -2  0 |
+        // | 0  3 -4 |
+        // | 5  0  0 |
+        let mat: CsMat<f64> = CsMat::new((3, 3),
+                                         vec![0, 2, 4, 5],
+                                         vec![0, 1, 1, 2, 0],
+                                         vec![1., -2., 3., -4., 5.]);
+        let dense = mat.to_dense();
+        let (mut dense_inf, mut dense_one) = (0f64, 0f64);
+        for i in 0..3 {
+            let row_sum: f64 = dense.row(i).iter().map(|x| x.abs()).sum();
+            let col_sum: f64 = dense.column(i).iter().map(|x| x.abs()).sum();
+            dense_inf = dense_inf.max(row_sum);
+            dense_one = dense_one.max(col_sum);
+        }
+        assert_eq!(dense_inf, 7.);
+        assert_eq!(dense_one, 6.);
+        assert_eq!(mat.norm_inf(), dense_inf);
+        assert_eq!(mat.norm_one(), dense_one);
+        assert_eq!(mat.to_csc().norm_inf(), dense_inf);
+        assert_eq!(mat.to_csc().norm_one(), dense_one);
+        let empty: CsMat<f64> = CsMat::zero((2, 2));
+        assert_eq!(empty.norm_inf(), 0.);
     }
 
     #[test]
-    fn index() {
-        // | 0 2 0 |
-        // | 1 0 0 |
-        // | 0 3 4 |
-        let mat = CsMat::new_csc((3, 3),
-                                 vec![0, 1, 3, 4],
-                                 vec![1, 0, 2, 2],
-                                 vec![1., 2., 3., 4.]);
-        assert_eq!(mat[[1, 0]], 1.);
-        assert_eq!(mat[[0, 1]], 2.);
-        assert_eq!(mat[[2, 1]], 3.);
-        assert_eq!(mat[[2, 2]], 4.);
-        assert_eq!(mat.get(0, 0), None);
-        assert_eq!(mat.get(4, 4), None);
+    fn grow() {
+        // | 1 2 |
+        // | 0 3 |
+        let mat = CsMat::new((2, 2), vec![0, 2, 3], vec![0, 1, 1],
+                             vec![1., 2., 3.]);
+        let grown = mat.grow(4, 4).unwrap();
+        assert_eq!(grown.shape(), (4, 4));
+        grown.check_compressed_structure().unwrap();
+        assert_eq!(grown.to_dense(), arr2(&[[1., 2., 0., 0.],
+                                            [0., 3., 0., 0.],
+                                            [0., 0., 0., 0.],
+                                            [0., 0., 0., 0.]]));
+        let grown_csc = mat.to_csc().grow(3, 4).unwrap();
+        grown_csc.check_compressed_structure().unwrap();
+        assert_eq!(grown_csc.get(1, 1), Some(&3.));
+        assert_eq!(grown_csc.indptr(), &[0, 1, 3, 3, 3]);
+        assert!(mat.grow(1, 4).is_none());
+        assert!(mat.grow(4, 1).is_none());
     }
 
     #[test]
-    fn get_mut() {
-        // | 0 1 0 |
-        // | 1 0 0 |
-        // | 0 1 1 |
-        let mut mat = CsMat::new_csc((3, 3),
-                                     vec![0, 1, 3, 4],
-                                     vec![1, 0, 2, 2],
-                                     vec![1.; 4]);
+    fn fold_entries() {
+        let mat = mat1();
+        let nnz = mat.fold_entries(0, |count, _, _, _| count + 1);
+        assert_eq!(nnz, mat.nnz());
+        let sum = mat.fold_entries(0., |acc, _, _, &val| acc + val);
+        assert_eq!(sum, 34.);
+        let diag_sum = mat1_csc().fold_entries(0., |acc, row, col, &val| {
+            if row == col { acc + val } else { acc }
+        });
+        assert_eq!(diag_sum, 5.);
+    }
 
-        *mat.get_mut(2, 1).unwrap() = 3.;
+    #[test]
+    fn filter() {
+        // | 0.5  3   0 |
+        // |  0 -0.1  0 |
+        // |  2   0  -4 |
+        let mat = CsMat::new((3, 3),
+                             vec![0, 2, 3, 5],
+                             vec![0, 1, 1, 0, 2],
+                             vec![0.5, 3., -0.1, 2., -4.]);
+        let res = mat.filter(|_, _, &val: &f64| val.abs() >= 1.);
+        assert_eq!(res.nnz(), 3);
+        assert_eq!(res, CsMat::new((3, 3),
+                                   vec![0, 1, 1, 3],
+                                   vec![1, 0, 2],
+                                   vec![3., 2., -4.]));
+        let res_csc = mat.to_csc().filter(|_, _, &val: &f64| val.abs() >= 1.);
+        assert_eq!(res_csc, res.to_csc());
+        let col_zero = mat.filter(|_, col, _| col == 0);
+        assert_eq!(col_zero.data(), &[0.5, 2.]);
+    }
 
-        let exp = CsMat::new_csc((3, 3),
-                                 vec![0, 1, 3, 4],
-                                 vec![1, 0, 2, 2],
-                                 vec![1., 1., 3., 1.]);
+    #[test]
+    fn from_sorted_iter() {
+        let triplets = vec![(0, 2, 3.), (0, 3, 4.), (1, 3, 2.), (1, 4, 5.),
+                            (2, 2, 5.), (3, 1, 8.), (4, 3, 7.)];
+        let mat = CsMat::from_sorted_iter((5, 5), CSR, triplets.clone());
+        assert_eq!(mat.unwrap(), mat1());
+
+        let csc_order = vec![(3, 1, 8.), (0, 2, 3.), (2, 2, 5.), (0, 3, 4.),
+                             (1, 3, 2.), (4, 3, 7.), (1, 4, 5.)];
+        let mat = CsMat::from_sorted_iter((5, 5), CSC, csc_order);
+        assert_eq!(mat.unwrap(), mat1_csc());
+
+        // trailing empty outer dimensions
+        let mat = CsMat::from_sorted_iter((3, 2), CSR, vec![(0, 1, 1.)]);
+        assert_eq!(mat.unwrap().indptr(), &[0, 1, 1, 1]);
+    }
 
-        assert_eq!(mat, exp);
+    #[test]
+    fn from_sorted_iter_fail() {
+        let unsorted_outer = vec![(1, 0, 1.), (0, 1, 1.)];
+        let res = CsMat::from_sorted_iter((2, 2), CSR, unsorted_outer);
+        assert_eq!(res, Err(SprsError::NonSortedIndices));
+        let unsorted_inner = vec![(0, 1, 1.), (0, 0, 1.)];
+        let res = CsMat::from_sorted_iter((2, 2), CSR, unsorted_inner);
+        assert_eq!(res, Err(SprsError::NonSortedIndices));
+        let duplicate = vec![(0, 1, 1.), (0, 1, 1.)];
+        let res = CsMat::from_sorted_iter((2, 2), CSR, duplicate);
+        assert_eq!(res, Err(SprsError::NonSortedIndices));
+        let out_of_bounds = vec![(0, 1, 1.), (2, 1, 1.)];
+        let res = CsMat::from_sorted_iter((2, 2), CSR, out_of_bounds);
+        assert_eq!(res, Err(SprsError::OutOfBoundsIndex));
+    }
 
-        mat[[2, 2]] = 5.;
-        let exp = CsMat::new_csc((3, 3),
-                                 vec![0, 1, 3, 4],
-                                 vec![1, 0, 2, 2],
-                                 vec![1., 1., 3., 5.]);
+    #[test]
+    fn to_other_storage_inplace() {
+        let mut mat = mat1();
+        mat.to_other_storage_inplace();
+        assert_eq!(mat, mat1_csc());
+        mat.check_compressed_structure().unwrap();
+        mat.to_other_storage_inplace();
+        assert_eq!(mat, mat1());
+
+        // the same arrays give the transpose when the storage is unchanged
+        let mut mat = mat1();
+        mat.to_other_storage_inplace();
+        assert_eq!(mat.transpose_into(), mat1().transpose_same_storage());
+    }
 
-        assert_eq!(mat, exp);
+    #[test]
+    #[should_panic]
+    fn to_other_storage_inplace_non_square() {
+        let mut mat: CsMat<f64> = CsMat::zero((2, 3));
+        mat.to_other_storage_inplace();
     }
 
     #[test]
-    fn map() {
-        // | 0 1 0 |
-        // | 1 0 0 |
-        // | 0 1 1 |
-        let mat = CsMat::new_csc((3, 3),
-                                 vec![0, 1, 3, 4],
-                                 vec![1, 0, 2, 2],
-                                 vec![1.; 4]);
+    fn is_diagonally_dominant() {
+        // | 4 -1  1 |
+        // | 1  3 -2 |
+        // | 0  2 -5 |
+        let mat = CsMat::new((3, 3),
+                             vec![0, 3, 6, 8],
+                             vec![0, 1, 2, 0, 1, 2, 1, 2],
+                             vec![4., -1., 1., 1., 3., -2., 2., -5.]);
+        assert!(mat.is_diagonally_dominant(false));
+        assert!(!mat.is_diagonally_dominant(true));
+        assert!(!mat.to_csc().is_diagonally_dominant(true));
+        assert!(mat.to_csc().is_diagonally_dominant(false));
+
+        let eye: CsMat<f64> = CsMat::eye(3);
+        assert!(eye.is_diagonally_dominant(true));
+
+        // | 1 3 |
+        // | 0 1 |
+        let mat = CsMat::new((2, 2), vec![0, 2, 3], vec![0, 1, 1],
+                             vec![1., 3., 1.]);
+        assert!(!mat.is_diagonally_dominant(false));
+
+        // missing diagonal element
+        let mat = CsMat::new((2, 2), vec![0, 1, 2], vec![0, 0],
+                             vec![1., 1.]);
+        assert!(!mat.is_diagonally_dominant(false));
+    }
 
-        let mut res = mat.map(|&x| x + 2.);
-        let expected = CsMat::new_csc((3, 3),
-                                      vec![0, 1, 3, 4],
-                                      vec![1, 0, 2, 2],
-                                      vec![3.; 4]);
-        assert_eq!(res, expected);
+    #[test]
+    fn jacobi_step() {
+        // | 4 1 0 |       | 1 |
+        // | 1 5 2 | x_0 = | 2 |
+        // | 0 2 6 |       | 3 |
+        let mat = CsMat::new((3, 3),
+                             vec![0, 2, 5, 7],
+                             vec![0, 1, 0, 1, 2, 1, 2],
+                             vec![4., 1., 1., 5., 2., 2., 6.]);
+        let x_true = [1f64, 2., 3.];
+        let b = mat.mul_dense_vec(&x_true);
+        let mut x = vec![0.; 3];
+        for _ in 0..50 {
+            x = mat.jacobi_step(&x, &b).unwrap();
+        }
+        for (xi, xi_true) in x.iter().zip(x_true.iter()) {
+            assert!((xi - xi_true).abs() < 1e-10);
+        }
+        let x_csc = mat.to_csc().jacobi_step(&[0.; 3], &b).unwrap();
+        assert_eq!(x_csc, mat.jacobi_step(&[0.; 3], &b).unwrap());
+    }
 
-        res.map_inplace(|&x| x / 3.);
-        assert_eq!(res, mat);
+    #[test]
+    fn jacobi_step_singular() {
+        let missing = CsMat::new((2, 2), vec![0, 1, 2], vec![0, 0],
+                                 vec![1., 1.]);
+        let res = missing.jacobi_step(&[0., 0.], &[1., 1.]);
+        assert_eq!(res, Err(SprsError::SingularMatrix));
+        let zero = CsMat::new((2, 2), vec![0, 1, 2], vec![0, 1],
+                              vec![1., 0.]);
+        let res = zero.jacobi_step(&[0., 0.], &[1., 1.]);
+        assert_eq!(res, Err(SprsError::SingularMatrix));
     }
 
     #[test]
-    fn insert() {
-        // | 0 1 0 |
-        // | 1 0 0 |
-        // | 0 1 1 |
-        let mut mat = CsMat::empty(CSR, 0);
-        mat.reserve_outer_dim(3);
-        mat.reserve_nnz(4);
-        // exercise the fast and easy path where the elements are added
-        // in row order for a CSR matrix
-        mat.insert(0, 1, 1.);
-        mat.insert(1, 0, 1.);
-        mat.insert(2, 1, 1.);
-        mat.insert(2, 2, 1.);
+    fn conj() {
+        use num_kinds::Conjugate;
 
-        let expected = CsMat::new((3, 3),
-                                  vec![0, 1, 2, 4],
-                                  vec![1, 0, 1, 2],
-                                  vec![1.; 4]);
-        assert_eq!(mat, expected);
+        #[derive(Debug, Clone, Copy, PartialEq)]
+        struct MockComplex {
+            re: f64,
+            im: f64,
+        }
 
-        // | 2 1 0 |
-        // | 1 0 0 |
-        // | 0 1 1 |
-        // exercise adding inside an already formed row (ie a search needs
-        // to be performed)
-        mat.insert(0, 0, 2.);
-        let expected = CsMat::new((3, 3),
-                                  vec![0, 2, 3, 5],
-                                  vec![0, 1, 0, 1, 2],
-                                  vec![2., 1., 1., 1., 1.]);
-        assert_eq!(mat, expected);
+        impl Conjugate for MockComplex {
+            fn conj(&self) -> MockComplex {
+                MockComplex { re: self.re, im: -self.im }
+            }
+        }
 
-        // | 2 1 0 |
-        // | 3 0 0 |
-        // | 0 1 1 |
-        // exercise the fact that inserting in an existing element
-        // should change this element's value
-        mat.insert(1, 0, 3.);
-        let expected = CsMat::new((3, 3),
-                                  vec![0, 2, 3, 5],
-                                  vec![0, 1, 0, 1, 2],
-                                  vec![2., 1., 3., 1., 1.]);
-        assert_eq!(mat, expected);
+        let c = |re, im| MockComplex { re: re, im: im };
+        let mat = CsMatI::new_csc((2, 2), vec![0, 1, 3], vec![0, 0, 1],
+                                  vec![c(1., 2.), c(3., 0.), c(0., -1.)]);
+        let res = mat.conj();
+        assert_eq!(res.indices(), mat.indices());
+        assert_eq!(res.data(), &[c(1., -2.), c(3., 0.), c(0., 1.)]);
+
+        let real = mat1();
+        assert_eq!(real.conj(), real);
+
+        use num_complex::{Complex32, Complex64};
+        let mat = CsMat::new((1, 2), vec![0, 2], vec![0, 1],
+                             vec![Complex64::new(1., 2.),
+                                  Complex64::new(-3., -4.)]);
+        assert_eq!(mat.conj().data(), &[Complex64::new(1., -2.),
+                                        Complex64::new(-3., 4.)]);
+        let mat = CsMat::new((1, 1), vec![0, 1], vec![0],
+                             vec![Complex32::new(1., 2.)]);
+        assert_eq!(mat.conj().data(), &[Complex32::new(1., -2.)]);
     }
 
     #[test]
-    /// Non-regression test for https://github.com/vbarrielle/sprs/issues/129
-    fn bug_129() {
-        let mut mat = CsMat::zero((3, 100));
-        mat.insert(2, 3, 42);
-        let mut iter = mat.iter();
-        assert_eq!(iter.next(), Some((&42, (2, 3))));
-        assert_eq!(iter.next(), None);
+    fn bandwidth() {
+        let eye: CsMat<f64> = CsMat::eye(4);
+        assert_eq!(eye.bandwidth(), (0, 0));
+        // | 2 1 0 0 |
+        // | 1 2 1 0 |
+        // | 0 1 2 1 |
+        // | 0 0 1 2 |
+        let tridiag = CsMat::new((4, 4),
+                                 vec![0, 2, 5, 8, 10],
+                                 vec![0, 1, 0, 1, 2, 1, 2, 3, 2, 3],
+                                 vec![2., 1., 1., 2., 1., 1., 2., 1., 1., 2.]);
+        assert_eq!(tridiag.bandwidth(), (1, 1));
+        assert_eq!(tridiag.to_csc().bandwidth(), (1, 1));
+        assert_eq!(mat1().bandwidth(), (2, 3));
     }
 
     #[test]
-    fn iter_mut() {
-        // | 0 1 0 |
-        // | 1 0 0 |
-        // | 0 1 1 |
-        let mut mat = CsMat::new_csc((3, 3),
-                                     vec![0, 1, 3, 4],
-                                     vec![1, 0, 2, 2],
-                                     vec![1.; 4]);
+    fn check_compressed_structure_raw_view() {
+        let indptr: &[usize] = &[0, 2, 3, 4];
+        let data: &[f64] = &[1., 2., 3., 4.];
+        let valid: &[usize] = &[0, 2, 1, 0];
+        let view = unsafe {
+            CsMatView::new_view_raw(CSR, (3, 3), indptr.as_ptr(),
+                                    valid.as_ptr(), data.as_ptr())
+        };
+        assert_eq!(view.check_compressed_structure(), Ok(()));
 
-        for mut col_vec in mat.outer_iterator_mut() {
-            for (row_ind, val) in col_vec.iter_mut() {
-                *val = row_ind as f64 + 1.;
-            }
-        }
+        let unsorted: &[usize] = &[2, 0, 1, 0];
+        let view = unsafe {
+            CsMatView::new_view_raw(CSR, (3, 3), indptr.as_ptr(),
+                                    unsorted.as_ptr(), data.as_ptr())
+        };
+        assert_eq!(view.check_compressed_structure(),
+                   Err(SprsError::NonSortedIndices));
 
-        let expected = CsMat::new_csc((3, 3),
-                                      vec![0, 1, 3, 4],
-                                      vec![1, 0, 2, 2],
-                                      vec![2., 1., 3., 3.]);
-        assert_eq!(mat, expected);
+        let out_of_bounds: &[usize] = &[0, 2, 3, 0];
+        let view = unsafe {
+            CsMatView::new_view_raw(CSR, (3, 3), indptr.as_ptr(),
+                                    out_of_bounds.as_ptr(), data.as_ptr())
+        };
+        assert_eq!(view.check_compressed_structure(),
+                   Err(SprsError::OutOfBoundsIndex));
+
+        // the first index is out of bounds, but not part of any row
+        let offset_indptr: &[usize] = &[1, 2, 3];
+        let offset_indices: &[usize] = &[999, 0, 1];
+        let view = unsafe {
+            CsMatView::new_view_raw(CSR, (2, 2), offset_indptr.as_ptr(),
+                                    offset_indices.as_ptr(), data.as_ptr())
+        };
+        assert_eq!(view.check_compressed_structure(),
+                   Err(SprsError::OutOfBoundsIndptr));
+        let res = CsMatView::new_view(CSR, (2, 2), offset_indptr,
+                                      offset_indices, &data[..3]);
+        assert_eq!(res, Err(SprsError::OutOfBoundsIndptr));
     }
 
     #[test]
-    #[should_panic]
-    fn modify_fail() {
-        let mut mat = CsMat::new_csc((3, 3),
-                                      vec![0, 1, 3, 4],
-                                      vec![1, 0, 2, 2],
-                                      vec![1.; 4]);
-
-        // we panic because we forget to modify the last index, which gets
-        // pushed in the same col as its predecessor, yet has the same value
-        mat.modify(|indptr, indices, data| {
-            indptr[1] = 2;
-            indptr[2] = 4;
-            indices[0] = 0;
-            indices[1] = 1;
-            data[2] = 2.;
-        });
+    fn powi() {
+        // | -2 1 |
+        // |  0 2 |
+        let mat = CsMat::new((2, 2), vec![0, 2, 3], vec![0, 1, 1],
+                             vec![-2., 1., 2.]);
+        let squared = mat.powi(2);
+        assert_eq!(squared.data(), &[4., 1., 4.]);
+        assert_eq!(squared.indices(), mat.indices());
+        // the matrix product differs
+        assert!(squared != &mat * &mat);
+        assert_eq!(mat.powi(3).data(), &[-8., 1., 8.]);
     }
 
     #[test]
-    fn convert_types() {
-        let mat: CsMat<f32> = CsMat::eye(3);
-        let mat_: CsMatI<f64, u32> = mat.to_other_types();
-        assert_eq!(mat_.indptr(), &[0, 1, 2, 3]);
-
-        let mat = CsMatI::new_csc((3, 3),
-                                  vec![0u32, 1, 3, 4],
-                                  vec![1, 0, 2, 2],
-                                  vec![1.; 4]);
-        let mat_: CsMatI<f32, usize> = mat.to_other_types();
-        assert_eq!(mat_.indptr(), &[0, 1, 3, 4]);
-        assert_eq!(mat_.data(), &[1.0f32, 1., 1., 1.]);
+    fn densify_into() {
+        let mut buf = vec![0.; 25];
+        mat1().densify_into(&mut buf);
+        assert_eq!(&buf[..], mat1().to_dense().as_slice().unwrap());
+        // reusing the buffer should not leave values of the first matrix
+        let eye: CsMat<f64> = CsMat::eye_csc(5);
+        eye.densify_into(&mut buf);
+        assert_eq!(&buf[..], eye.to_dense().as_slice().unwrap());
     }
 
     #[test]
-    fn iter() {
-        let mat = CsMat::new_csc((3, 3),
-                                 vec![0, 1, 3, 4],
-                                 vec![1, 0, 2, 2],
-                                 vec![1.; 4]);
-        let mut iter = mat.iter();
-        assert_eq!(iter.next(), Some((&1., (1, 0))));
-        assert_eq!(iter.next(), Some((&1., (0, 1))));
-        assert_eq!(iter.next(), Some((&1., (2, 1))));
-        assert_eq!(iter.next(), Some((&1., (2, 2))));
-        assert_eq!(iter.next(), None);
+    #[should_panic]
+    fn densify_into_fail() {
+        let mut buf = vec![0.; 24];
+        mat1().densify_into(&mut buf);
     }
 }
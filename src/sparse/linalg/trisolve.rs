@@ -317,7 +317,6 @@ where N: Copy + Num,
     // solve for the non-zero values into dense workspace
     rhs.scatter(x_workspace);
     for &ind in dstack.iter_right().map(stack::extract_stack_val) {
-        println!("ind: {}", ind);
         let col = lower_tri_mat.outer_view(ind).expect("ind not in bounds");
         try!(lspsolve_csc_process_col(col, ind, x_workspace));
     }
@@ -91,6 +91,11 @@ where IndStorage: Deref<Target=[I]> {
         }
     }
 
+    /// The dimension of this permutation
+    pub fn dim(&self) -> usize {
+        self.dim
+    }
+
     pub fn inv(&self) -> PermViewI<I> {
         match &self.storage {
             &Identity => PermViewI {
@@ -423,6 +423,30 @@ mod test {
         assert_eq!(expected_output, res);
     }
 
+    #[test]
+    fn mul_csr_csc_empty_row() {
+        // | 1 0 2 |   | 0 1 0 |   | 0 1 6 |
+        // | 0 0 0 | * | 0 0 2 | = | 0 0 0 |
+        // | 0 3 0 |   | 0 0 3 |   | 0 0 6 |
+        let a = CsMat::new((3, 3),
+                           vec![0, 2, 2, 3],
+                           vec![0, 2, 1],
+                           vec![1., 2., 3.]);
+        let b = CsMat::new_csc((3, 3),
+                               vec![0, 0, 1, 3],
+                               vec![0, 1, 2],
+                               vec![1., 2., 3.]);
+        let res = &a * &b;
+        assert!(res.is_csr());
+        assert_eq!(res.indptr(), &[0, 2, 2, 3]);
+        assert_eq!(res.indices(), &[1, 2, 2]);
+        let expected_output = arr2(&[[0., 1., 6.],
+                                     [0., 0., 0.],
+                                     [0., 0., 6.]]);
+        assert_eq!(res.to_dense(), expected_output);
+        assert_eq!(res.to_dense(), a.to_dense().dot(&b.to_dense()));
+    }
+
     #[test]
     fn mul_csc_csc() {
         let a = mat1_csc();
@@ -488,6 +512,37 @@ mod test {
         assert_eq!(expected_output, res);
     }
 
+    #[test]
+    fn mul_mat_csvec_single_nnz() {
+        // | 1 0 2 |
+        // | 0 0 3 |
+        // | 0 4 0 |
+        let a = CsMat::new((3, 3),
+                           vec![0, 2, 3, 4],
+                           vec![0, 2, 2, 1],
+                           vec![1., 2., 3., 4.]);
+        let v = CsVec::new(3, vec![2], vec![2.]);
+        let expected_output = CsVec::new(3, vec![0, 1], vec![4., 6.]);
+        assert_eq!(&a * &v, expected_output);
+        assert_eq!(&a.to_csc() * &v, expected_output);
+    }
+
+    #[test]
+    #[should_panic]
+    fn mul_csr_csvec_dim_mismatch() {
+        let a = mat1();
+        let v = CsVec::new(4, vec![0, 2], vec![1.; 2]);
+        let _res = &a * &v;
+    }
+
+    #[test]
+    #[should_panic]
+    fn mul_csc_csvec_dim_mismatch() {
+        let a = mat1_csc();
+        let v = CsVec::new(4, vec![0, 2], vec![1.; 2]);
+        let _res = &a * &v;
+    }
+
     #[test]
     fn mul_csr_dense_rowmaj() {
         let a = Array::eye(3);
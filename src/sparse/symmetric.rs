@@ -31,7 +31,7 @@ DStorage: Deref<Target=[N]> {
 
 #[cfg(test)]
 mod test {
-    use sparse::CsMatView;
+    use sparse::{CsMat, CsMatView};
     use sparse::csmat::CompressedStorage::{CSR};
     use super::is_symmetric;
 
@@ -70,5 +70,34 @@ mod test {
         assert!(is_symmetric(&a));
     }
 
+    #[test]
+    fn is_symmetric_asymmetric_pattern() {
+        // | 1 2 |
+        // | 0 3 |
+        let a = CsMat::new((2, 2), vec![0, 2, 3], vec![0, 1, 1],
+                           vec![1., 2., 3.]);
+        assert!(!is_symmetric(&a));
+        assert!(!a.is_symmetric());
+        assert!(!a.to_csc().is_symmetric());
+    }
+
+    #[test]
+    fn is_symmetric_asymmetric_values() {
+        // | 1 2 |
+        // | 4 3 |
+        let a = CsMat::new((2, 2), vec![0, 2, 4], vec![0, 1, 0, 1],
+                           vec![1., 2., 4., 3.]);
+        assert!(!is_symmetric(&a));
+        let b = CsMat::new((2, 2), vec![0, 2, 4], vec![0, 1, 0, 1],
+                           vec![1., 2., 2., 3.]);
+        assert!(b.is_symmetric());
+    }
+
+    #[test]
+    fn is_symmetric_non_square() {
+        let a: CsMat<f64> = CsMat::zero((2, 3));
+        assert!(!a.is_symmetric());
+    }
+
     // TODO: symmetry test on A^T*A products
 }
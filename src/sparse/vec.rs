@@ -24,10 +24,10 @@ use std::slice::{self, Iter, IterMut};
 use std::collections::HashSet;
 use std::hash::Hash;
 use std::marker::PhantomData;
-use ndarray::{self, ArrayBase};
+use ndarray::{self, Array, ArrayBase};
 use ::{Ix1};
 
-use num_traits::{Num, Zero};
+use num_traits::{Num, Zero, Float};
 
 use indexing::SpIndex;
 use array_backend::Array2;
@@ -137,6 +137,12 @@ pub trait SparseIterTools: Iterator {
     /// Iterate over non-zero elements of either of two vectors.
     /// This is useful for implementing eg addition of vectors.
     ///
+    /// Each index of the union of the non-zero patterns is yielded once, in
+    /// increasing order, as a `NnzEither::Left` if only the first vector
+    /// has a non-zero there, as a `NnzEither::Right` if only the second
+    /// vector has one, and as a `NnzEither::Both` if both have one. The
+    /// intersection of the patterns can be iterated using `nnz_zip`.
+    ///
     /// # Example
     ///
     /// ```rust
@@ -392,6 +398,17 @@ where Ite1: Iterator<Item=(usize, &'a N1)>,
     life: PhantomData<(&'a N1, &'a N2)>,
 }
 
+/// The norms that can be computed on a sparse vector
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Norm {
+    /// Sum of the absolute values
+    L1,
+    /// Square root of the sum of the squared values
+    L2,
+    /// Maximum absolute value
+    Inf,
+}
+
 #[derive(PartialEq, Debug)]
 pub enum NnzEither<'a, N1: 'a, N2: 'a> {
     Both((usize, &'a N1, &'a N2)),
@@ -483,6 +500,31 @@ impl<N, I: SpIndex> CsVecBase<Vec<I>, Vec<N>> {
         v.check_structure().and(Ok(v)).unwrap()
     }
 
+    /// Create an owning CsVec holding the non-zero values of a dense vector.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sprs::CsVec;
+    /// let vec: CsVec<f64> = CsVec::from_dense(&[0., 3., 0., 5.][..]);
+    /// assert_eq!(vec.dim(), 4);
+    /// assert_eq!(vec.indices(), &[1, 3]);
+    /// assert_eq!(vec.data(), &[3., 5.]);
+    /// ```
+    pub fn from_dense<T>(dense: T) -> CsVecI<N, I>
+    where T: DenseVector<N>,
+          N: Zero + Clone
+    {
+        let mut res = CsVecI::empty(dense.dim());
+        for ind in 0..dense.dim() {
+            let val = dense.index(ind);
+            if !val.is_zero() {
+                res.append(ind, val.clone());
+            }
+        }
+        res
+    }
+
     /// Create an empty CsVec, which can be used for incremental construction
     pub fn empty(dim: usize) -> CsVecI<N, I> {
         CsVecI {
@@ -601,9 +643,13 @@ where I: SpIndex,
     }
 
     /// Check the sparse structure, namely that:
+    /// - indices and data have the same length
     /// - indices is sorted
     /// - indices are lower than dims()
     pub fn check_structure(&self) -> Result<(), SprsError> {
+        if self.indices.len() != self.data.len() {
+            return Err(SprsError::DataIndicesMismatch);
+        }
         if ! self.indices.windows(2).all(|x| x[0] < x[1]) {
             return Err(SprsError::NonSortedIndices);
         }
@@ -614,7 +660,7 @@ where I: SpIndex,
 
         let max_ind = self.indices.iter().max().unwrap_or(&I::zero()).index();
         if max_ind >= self.dim {
-            panic!("Out of bounds index");
+            return Err(SprsError::OutOfBoundsIndex);
         }
 
         Ok(())
@@ -688,6 +734,9 @@ where I: SpIndex,
     }
 
     /// Access element at given index, with logarithmic complexity
+    ///
+    /// The element is found by a binary search over the sorted indices,
+    /// and `None` is returned if it is not stored.
     pub fn get<'a>(&'a self, index: usize) -> Option<&'a N>
     where I: 'a
     {
@@ -713,6 +762,11 @@ where I: SpIndex,
     /// However, even if dense vectors work, it is more performant to use
     /// the [`dot_dense`](struct.CsVecBase.html#method.dot_dense).
     ///
+    /// When both vectors are sparse, their sorted indices are walked
+    /// simultaneously, hence the complexity is linear in the sum of their
+    /// numbers of non-zeros. Vectors with disjoint supports have a
+    /// null dot product.
+    ///
     /// # Panics
     ///
     /// If the dimension of the vectors do not match.
@@ -773,6 +827,46 @@ where I: SpIndex,
         }
     }
 
+    /// Create a dense vector of dimension `self.dim()` holding the same
+    /// values as this vector, with zeros at the non-stored locations.
+    pub fn to_dense(&self) -> Array<N, Ix1>
+    where N: Clone + Zero
+    {
+        let mut res = Array::zeros(self.dim());
+        self.scatter(res.as_slice_mut().unwrap());
+        res
+    }
+
+    /// Compute the given norm of this vector.
+    ///
+    /// Only the non-zero values need to be visited, as the implicit zeros
+    /// do not contribute to any of the supported norms.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use sprs::CsVec;
+    /// use sprs::vec::Norm;
+    /// let vec = CsVec::new(6, vec![0, 2, 5], vec![3., -4., 1.]);
+    /// assert_eq!(vec.norm(Norm::L1), 8.);
+    /// assert_eq!(vec.norm(Norm::Inf), 4.);
+    /// ```
+    pub fn norm(&self, p: Norm) -> N
+    where N: Float
+    {
+        match p {
+            Norm::L1 => {
+                self.data.iter().fold(N::zero(), |acc, &x| acc + x.abs())
+            }
+            Norm::L2 => {
+                self.data.iter().fold(N::zero(), |acc, &x| acc + x * x).sqrt()
+            }
+            Norm::Inf => {
+                self.data.iter().fold(N::zero(), |acc, &x| acc.max(x.abs()))
+            }
+        }
+    }
+
     /// Transform this vector into a set of (index, value) tuples
     pub fn to_set(self) -> HashSet<(usize, N)>
     where N: Hash + Eq + Clone {
@@ -823,6 +917,17 @@ where N: 'a,
         }
     }
 
+    /// Sparse vector self-multiplication by a scalar. The sparse structure
+    /// is kept, even when scaling by zero.
+    ///
+    /// A scaled copy can be obtained using `map`, eg
+    /// `vec.map(|&x| x * alpha)`.
+    pub fn scale(&mut self, alpha: N) where N: Num + Copy {
+        for val in self.data_mut() {
+            *val = *val * alpha;
+        }
+    }
+
     /// Apply a function to each non-zero element, mutating it
     pub fn map_inplace<F>(&mut self, mut f: F)
     where F: FnMut(&N) -> N
@@ -1183,7 +1288,7 @@ mod alga_impls {
 mod test {
     use sparse::{CsVec, CsVecI};
     use super::SparseIterTools;
-    use ndarray::Array;
+    use ndarray::{Array, arr1};
     use num_traits::Zero;
 
     fn test_vec1() -> CsVec<f64> {
@@ -1213,6 +1318,18 @@ mod test {
         assert!(iter.next().is_none());
     }
 
+    #[test]
+    fn nnz_or_zip_one_empty() {
+        use super::NnzEither::*;
+        let vec1 = CsVec::new(4, vec![1, 3], vec![1., 3.]);
+        let empty: CsVec<f64> = CsVec::empty(4);
+        let res: Vec<_> = vec1.iter().nnz_or_zip(empty.iter()).collect();
+        assert_eq!(res, vec![Left((1, &1.)), Left((3, &3.))]);
+        let res: Vec<_> = empty.iter().nnz_or_zip(vec1.iter()).collect();
+        assert_eq!(res, vec![Right((1, &1.)), Right((3, &3.))]);
+        assert_eq!(empty.iter().nnz_or_zip(empty.iter()).count(), 0);
+    }
+
     #[test]
     fn test_nnz_or_zip_iter() {
         use super::NnzEither::*;
@@ -1255,6 +1372,67 @@ mod test {
         assert_eq!(16., vec1.dot_dense(ndarray_vec.view()));
     }
 
+    #[test]
+    fn to_dense() {
+        let vec = CsVec::new(4, vec![0, 2], vec![1., 3.]);
+        assert_eq!(vec.to_dense(), arr1(&[1., 0., 3., 0.]));
+        let empty: CsVec<f64> = CsVec::empty(3);
+        assert_eq!(empty.to_dense(), Array::zeros(3));
+    }
+
+    #[test]
+    fn from_dense() {
+        let vec: CsVec<f64> = CsVec::from_dense(vec![0., 3., 0., 5.]);
+        assert_eq!(vec.dim(), 4);
+        assert_eq!(vec.indices(), &[1, 3]);
+        assert_eq!(vec.data(), &[3., 5.]);
+        assert_eq!(CsVec::from_dense(vec.to_dense()), vec);
+
+        let vec: CsVecI<i32, u16> = CsVecI::from_dense(&[0, 0][..]);
+        assert_eq!(vec.dim(), 2);
+        assert_eq!(vec.nnz(), 0);
+    }
+
+    #[test]
+    fn norms() {
+        use super::Norm;
+        let vec = CsVec::new(8, vec![1, 3, 4, 6], vec![1., -2., 2., -4.]);
+        assert_eq!(vec.norm(Norm::L1), 9.);
+        assert_eq!(vec.norm(Norm::L2), 5.);
+        assert_eq!(vec.norm(Norm::Inf), 4.);
+
+        let empty: CsVec<f32> = CsVec::empty(3);
+        assert_eq!(empty.norm(Norm::L1), 0.);
+        assert_eq!(empty.norm(Norm::L2), 0.);
+        assert_eq!(empty.norm(Norm::Inf), 0.);
+    }
+
+    #[test]
+    fn scale() {
+        let mut vec = CsVec::new(5, vec![0, 2, 3], vec![1., -2., 4.]);
+        vec.scale(0.5);
+        assert_eq!(vec.indices(), &[0, 2, 3]);
+        assert_eq!(vec.data(), &[0.5, -1., 2.]);
+        vec.view_mut().scale(2.);
+        assert_eq!(vec.data(), &[1., -2., 4.]);
+        assert_eq!(vec.map(|&x| x * 3.).data(), &[3., -6., 12.]);
+        vec.scale(0.);
+        assert_eq!(vec.indices(), &[0, 2, 3]);
+        assert_eq!(vec.data(), &[0., 0., 0.]);
+    }
+
+    #[test]
+    fn dot_product_partial_overlap() {
+        let vec1 = CsVec::new(6, vec![0, 2, 3, 5], vec![1., 2., 3., 4.]);
+        let vec2 = CsVec::new(6, vec![1, 2, 5], vec![5., -1., 0.5]);
+        assert_eq!(0., vec1.dot(&vec2));
+        let vec3 = CsVec::new(6, vec![2, 4, 5], vec![3., 7., 2.]);
+        assert_eq!(14., vec1.dot(&vec3));
+        assert_eq!(14., vec3.dot(&vec1));
+        let empty: CsVec<f64> = CsVec::empty(6);
+        assert_eq!(0., vec1.dot(&empty));
+    }
+
     #[test]
     #[should_panic]
     fn dot_product_panics() {
@@ -1287,6 +1465,18 @@ mod test {
         assert_eq!(vec[index], 2.);
     }
 
+    #[test]
+    fn get() {
+        let vec = CsVec::new(8, vec![0, 2, 4, 6], vec![1., 2., 3., 4.]);
+        assert_eq!(vec.get(4), Some(&3.));
+        assert_eq!(vec.get(0), Some(&1.));
+        assert_eq!(vec.get(3), None);
+        assert_eq!(vec.get(7), None);
+        assert_eq!(vec.get(10), None);
+        let empty: CsVec<f64> = CsVec::empty(3);
+        assert_eq!(empty.get(1), None);
+    }
+
     #[test]
     fn get_mut() {
         let mut vec = CsVec::new(8, vec![0, 2, 4, 6], vec![1.; 4]);
@@ -1392,6 +1582,25 @@ mod test {
         (a, b, expected_sum)
     }
 
+    #[test]
+    fn adds_vectors_with_disjoint_supports() {
+        let a = CsVec::new(6, vec![0, 4], vec![1., 2.]);
+        let b = CsVec::new(6, vec![1, 2, 5], vec![3., 4., 5.]);
+        let sum = &a + &b;
+        assert_eq!(sum.indices(), &[0, 1, 2, 4, 5]);
+        assert_eq!(sum.data(), &[1., 3., 4., 2., 5.]);
+        assert_eq!(sum, &b + &a);
+        assert!(sum.check_structure().is_ok());
+    }
+
+    #[test]
+    #[should_panic]
+    fn adding_vectors_of_different_dims_panics() {
+        let a = CsVec::new(6, vec![0, 4], vec![1., 2.]);
+        let b = CsVec::new(5, vec![1], vec![3.]);
+        let _sum = &a + &b;
+    }
+
     #[test]
     fn negates_vectors() {
         let vector = CsVec::new(4, vec![0, 3], vec![2., -3.]);